@@ -0,0 +1,18 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Scene with cow.obj geometry embedded into MeshedObject.obj.
+fn scene_with_embedded_cow() -> sceneformat::Scene {
+    sceneformat::read("./examples/5.cowscene").unwrap()
+}
+
+fn decode_embedded_obj(c: &mut Criterion) {
+    let scene = scene_with_embedded_cow();
+    let json = sceneformat::encode_json(&scene).unwrap();
+    let binary = sceneformat::encode(&scene).unwrap();
+
+    c.bench_function("decode json with embedded cow.obj", |b| b.iter(|| sceneformat::decode(&json).unwrap()));
+    c.bench_function("decode binary with embedded cow.obj", |b| b.iter(|| sceneformat::decode(&binary).unwrap()));
+}
+
+criterion_group!(benches, decode_embedded_obj);
+criterion_main!(benches);
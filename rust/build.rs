@@ -1,10 +1,432 @@
-use std::io::Result;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+
+// Field numbers used in `SourceCodeInfo.Location.path`, see descriptor.proto
+const FILE_MESSAGE_TYPE: i32 = 4;
+const MESSAGE_FIELD: i32 = 2;
 
 fn main() -> Result<()> {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let descriptor_set_path = out_dir.join("scene_format_descriptor_set.bin");
+
+    let descriptor_set = compile_descriptor_set(&descriptor_set_path)?;
+
     let mut config = prost_build::Config::new();
-    config.type_attribute(".", "#[derive(serde::Deserialize, serde::Serialize)]");
+    config.type_attribute(".", "#[derive(serde::Serialize)]");
     config.type_attribute(".", "#[serde(rename_all=\"camelCase\")]");
+    add_json_attributes(&mut config, &descriptor_set);
     config.protoc_arg("-I=../proto");
     config.compile_protos(&["../proto/scene.proto"], &["src/"])?;
+
+    std::fs::write(out_dir.join("scene_format_annotations.rs"), generate_annotations(&descriptor_set))?;
+    std::fs::write(out_dir.join("scene_format_json.rs"), generate_json_deserialize(&descriptor_set))?;
+
+    println!("cargo:rerun-if-changed=../proto/scene.proto");
+    println!("cargo:rerun-if-changed=build.rs");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Runs protoc separately from prost_build, so that serde attributes can depend on the field types.
+fn compile_descriptor_set(descriptor_set_path: &Path) -> Result<FileDescriptorSet> {
+    let output = Command::new(prost_build::protoc())
+        .arg("--include_imports")
+        .arg("--include_source_info")
+        .arg("-o").arg(descriptor_set_path)
+        .arg("-I=../proto")
+        .arg("-I").arg(prost_build::protoc_include())
+        .arg("../proto/scene.proto")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::other(format!("protoc failed: {}", String::from_utf8_lossy(&output.stderr))));
+    }
+
+    Ok(FileDescriptorSet::decode(&*std::fs::read(descriptor_set_path)?)?)
+}
+
+/// Messages get `Deserialize` from `generate_json_deserialize`, oneof enums are derived,
+/// so that the form produced by serde for the generated types can still be read.
+fn add_json_attributes(config: &mut prost_build::Config, descriptor_set: &FileDescriptorSet) {
+    for file in &descriptor_set.file {
+        for message in &file.message_type {
+            for oneof in &message.oneof_decl {
+                // no leading dot, so that the path matches the oneof enum only
+                config.type_attribute(format!("{}.{}.{}", file.package(), message.name(), oneof.name()), "#[derive(serde::Deserialize)]");
+            }
+
+            for field in message.field.iter().filter(|field| is_number(field)) {
+                let field_path = match field.oneof_index {
+                    Some(oneof_index) => {
+                        let oneof = message.oneof_decl[oneof_index as usize].name();
+                        let field_path = format!("{}.{}.{}.{}", file.package(), message.name(), oneof, field.name());
+                        config.field_attribute(&field_path, "#[serde(deserialize_with=\"crate::json::number\")]");
+                        field_path
+                    },
+                    None => format!("{}.{}.{}", file.package(), message.name(), field.name()),
+                };
+
+                // NaN and infinities would be written as null otherwise
+                if matches!(field.r#type(), Type::Double | Type::Float) {
+                    config.field_attribute(field_path, "#[serde(serialize_with=\"crate::json::float\")]");
+                }
+            }
+        }
+    }
+}
+
+fn is_number(field: &FieldDescriptorProto) -> bool {
+    field.label() != Label::Repeated && matches!(field.r#type(),
+        Type::Double | Type::Float | Type::Int32 | Type::Int64 | Type::Uint32 | Type::Uint64 | Type::Sint32 | Type::Sint64)
+}
+
+/// Annotations found in the comments of a single field, as used by docs/html.tmpl.
+struct FieldAnnotations {
+    default: Option<String>,
+    optional: bool,
+    in_oneof: bool,
+}
+
+impl FieldAnnotations {
+    /// Fields which are neither `@optional` nor members of a oneof have to be set.
+    fn is_required(&self) -> bool {
+        !self.optional && !self.in_oneof
+    }
+}
+
+fn parse_annotations(comments: &str) -> FieldAnnotations {
+    let default = comments.lines()
+        .filter_map(|line| line.split("@default ").nth(1))
+        .map(|value| {
+            let value = value.trim();
+            if value.starts_with('(') {
+                value[..=value.find(')').expect("unterminated @default tuple")].to_string()
+            } else {
+                value.split_whitespace().next().unwrap_or("").to_string()
+            }
+        })
+        .next();
+
+    FieldAnnotations {
+        default,
+        optional: comments.contains("@optional"),
+        // matches both `@oneof` and `@oneof_item`
+        in_oneof: comments.contains("@oneof"),
+    }
+}
+
+struct Generator<'a> {
+    messages: HashMap<&'a str, &'a DescriptorProto>,
+    comments: HashMap<(usize, usize), String>,
+    message_indices: HashMap<&'a str, usize>,
+}
+
+impl<'a> Generator<'a> {
+
+    fn field_annotations(&self, message: &str, field_index: usize) -> FieldAnnotations {
+        let message_index = self.message_indices[message];
+        parse_annotations(self.comments.get(&(message_index, field_index)).map(String::as_str).unwrap_or(""))
+    }
+
+    /// A message can be created from scratch if every field of it has a documented default.
+    fn is_defaultable(&self, message: &str) -> bool {
+        let descriptor = self.messages[message];
+        descriptor.field.iter().enumerate().all(|(index, field)| {
+            field.label() != Label::Repeated && self.field_annotations(message, index).default.is_some()
+        })
+    }
+
+    fn default_value(&self, field: &FieldDescriptorProto, value: &str) -> String {
+        match field.r#type() {
+            Type::Double | Type::Float => {
+                let value: f64 = value.parse().expect("invalid floating point @default");
+                format!("{:?}", value)
+            },
+            Type::Int32 | Type::Int64 | Type::Uint32 | Type::Uint64 | Type::Sint32 | Type::Sint64 => {
+                let value: i64 = value.parse().expect("invalid integer @default");
+                value.to_string()
+            },
+            Type::Message => {
+                let type_name = message_type_name(field);
+                let components: Vec<&str> = value.trim_start_matches('(').trim_end_matches(')')
+                    .split(',')
+                    .map(str::trim)
+                    .collect();
+                let descriptor = self.messages[type_name];
+                assert_eq!(descriptor.field.len(), components.len(), "@default of {} has wrong number of components", field.name());
+
+                let fields: Vec<String> = descriptor.field.iter().zip(components)
+                    .map(|(component_field, component)| format!("{}: {}", component_field.name(), self.default_value(component_field, component)))
+                    .collect();
+                format!("{} {{ {} }}", type_name, fields.join(", "))
+            },
+            other => panic!("@default is not supported for fields of type {:?}", other),
+        }
+    }
+
+    fn generate_message(&self, code: &mut String, descriptor: &DescriptorProto) {
+        let name = descriptor.name();
+        let mut apply_defaults = String::new();
+        let mut collect_missing_fields = String::new();
+
+        for (index, field) in descriptor.field.iter().enumerate() {
+            if field.oneof_index.is_some() {
+                continue;
+            }
+
+            let annotations = self.field_annotations(name, index);
+            let field_name = field.name();
+
+            if field.r#type() != Type::Message {
+                if let Some(default) = &annotations.default {
+                    let value = self.default_value(field, default);
+                    if value != "0" && value != "0.0" {
+                        writeln!(apply_defaults, "        if self.{0} == {1} {{ self.{0} = {2}; }}", field_name, zero_value(field), value).unwrap();
+                    }
+                }
+                continue;
+            }
+
+            let type_name = message_type_name(field);
+            if field.label() == Label::Repeated {
+                writeln!(apply_defaults, "        for item in &mut self.{} {{ item.apply_defaults(); }}", field_name).unwrap();
+                writeln!(collect_missing_fields, "        for (index, item) in self.{0}.iter().enumerate() {{ item.collect_missing_fields(&format!(\"{{}}[{{}}]\", field_path(path, \"{0}\"), index), missing); }}", field_name).unwrap();
+                continue;
+            }
+
+            if let Some(default) = &annotations.default {
+                writeln!(apply_defaults, "        self.{}.get_or_insert({}).apply_defaults();", field_name, self.default_value(field, default)).unwrap();
+            } else if self.is_defaultable(type_name) {
+                writeln!(apply_defaults, "        self.{}.get_or_insert_with({}::default).apply_defaults();", field_name, type_name).unwrap();
+            } else {
+                writeln!(apply_defaults, "        if let Some(value) = &mut self.{} {{ value.apply_defaults(); }}", field_name).unwrap();
+                if annotations.is_required() {
+                    writeln!(collect_missing_fields, "        if self.{0}.is_none() {{ missing.push(field_path(path, \"{0}\")); }}", field_name).unwrap();
+                }
+            }
+
+            writeln!(collect_missing_fields, "        if let Some(value) = &self.{0} {{ value.collect_missing_fields(&field_path(path, \"{0}\"), missing); }}", field_name).unwrap();
+        }
+
+        for (oneof_index, oneof) in descriptor.oneof_decl.iter().enumerate() {
+            let oneof_name = oneof.name();
+            let enum_path = format!("{}::{}", to_snake_case(name), to_upper_camel_case(oneof_name));
+            let message_members: Vec<&FieldDescriptorProto> = descriptor.field.iter()
+                .filter(|field| field.oneof_index == Some(oneof_index as i32) && field.r#type() == Type::Message)
+                .collect();
+
+            if message_members.is_empty() {
+                continue;
+            }
+
+            if let [field] = message_members[..] {
+                let variant = format!("{}::{}", enum_path, to_upper_camel_case(field.name()));
+                writeln!(apply_defaults, "        if let Some({}(value)) = &mut self.{} {{ value.apply_defaults(); }}", variant, oneof_name).unwrap();
+            } else {
+                writeln!(apply_defaults, "        match &mut self.{} {{", oneof_name).unwrap();
+                for field in &message_members {
+                    let variant = format!("{}::{}", enum_path, to_upper_camel_case(field.name()));
+                    writeln!(apply_defaults, "            Some({}(value)) => value.apply_defaults(),", variant).unwrap();
+                }
+                writeln!(apply_defaults, "            _ => {{}},").unwrap();
+                writeln!(apply_defaults, "        }}").unwrap();
+            }
+
+            writeln!(collect_missing_fields, "        match &self.{} {{", oneof_name).unwrap();
+            for field in &message_members {
+                let variant = format!("{}::{}", enum_path, to_upper_camel_case(field.name()));
+                writeln!(collect_missing_fields, "            Some({}(value)) => value.collect_missing_fields(&field_path(path, \"{}\"), missing),", variant, field.name()).unwrap();
+            }
+            writeln!(collect_missing_fields, "            _ => {{}},").unwrap();
+            writeln!(collect_missing_fields, "        }}").unwrap();
+        }
+
+        writeln!(code, "impl {} {{", name).unwrap();
+        writeln!(code, "    /// Sets fields annotated with `@default` in scene.proto which are not set.").unwrap();
+        writeln!(code, "    pub fn apply_defaults(&mut self) {{").unwrap();
+        code.push_str(&apply_defaults);
+        writeln!(code, "    }}").unwrap();
+        writeln!(code).unwrap();
+        writeln!(code, "    /// Appends paths of fields which are required by scene.proto, but are not set.").unwrap();
+        writeln!(code, "    #[allow(unused_variables, clippy::ptr_arg, clippy::single_match)]").unwrap();
+        writeln!(code, "    pub fn collect_missing_fields(&self, path: &str, missing: &mut Vec<String>) {{").unwrap();
+        code.push_str(&collect_missing_fields);
+        writeln!(code, "    }}").unwrap();
+        writeln!(code, "}}").unwrap();
+        writeln!(code).unwrap();
+    }
+}
+
+fn generate_annotations(descriptor_set: &FileDescriptorSet) -> String {
+    let file = descriptor_set.file.iter()
+        .find(|file| file.name() == "scene.proto")
+        .expect("scene.proto is missing in descriptor set");
+
+    let mut comments = HashMap::new();
+    for location in file.source_code_info.iter().flat_map(|info| &info.location) {
+        if let [FILE_MESSAGE_TYPE, message_index, MESSAGE_FIELD, field_index] = location.path[..] {
+            let text = format!("{}\n{}", location.leading_comments(), location.trailing_comments());
+            comments.insert((message_index as usize, field_index as usize), text);
+        }
+    }
+
+    let generator = Generator {
+        messages: file.message_type.iter().map(|message| (message.name(), message)).collect(),
+        comments,
+        message_indices: file.message_type.iter().enumerate().map(|(index, message)| (message.name(), index)).collect(),
+    };
+
+    let mut code = String::new();
+    writeln!(code, "// Generated by build.rs from annotations in scene.proto, do not edit.").unwrap();
+    writeln!(code).unwrap();
+    writeln!(code, "fn field_path(path: &str, field: &str) -> String {{").unwrap();
+    writeln!(code, "    if path.is_empty() {{ field.to_string() }} else {{ format!(\"{{}}.{{}}\", path, field) }}").unwrap();
+    writeln!(code, "}}").unwrap();
+    writeln!(code).unwrap();
+
+    for message in &file.message_type {
+        generator.generate_message(&mut code, message);
+    }
+
+    code
+}
+
+/// `Deserialize` for every message, reading both the documented shorthand form (oneof members written
+/// directly into the owning message) and the canonical proto3 JSON mapping in a single pass.
+fn generate_json_deserialize(descriptor_set: &FileDescriptorSet) -> String {
+    let mut code = String::new();
+    writeln!(code, "// Generated by build.rs from scene.proto, do not edit.").unwrap();
+
+    for message in descriptor_set.file.iter().flat_map(|file| &file.message_type) {
+        let name = message.name();
+        let mut names = Vec::new();
+        let mut reads = Vec::new();
+
+        for field in &message.field {
+            let value = match field.oneof_index {
+                Some(oneof_index) => {
+                    let oneof = message.oneof_decl[oneof_index as usize].name();
+                    let variant = format!("{}::{}::{}", to_snake_case(name), to_upper_camel_case(oneof), to_upper_camel_case(field.name()));
+                    format!("crate::json::set_oneof::<A, _>(&mut self.{0}, {1}.map({2}), \"{3}.{0}\")?", oneof, json_read_optional(field), variant, name)
+                },
+                None if field.label() == Label::Repeated => format!("self.{} = {}.unwrap_or_default()", field.name(), json_read_repeated(field)),
+                None if field.r#type() == Type::Message => format!("self.{} = map.next_value()?", field.name()),
+                None => format!("self.{} = {}.unwrap_or_default()", field.name(), json_read_optional(field)),
+            };
+            names.push(json_names(field.name(), field.json_name()));
+            reads.push(value);
+        }
+
+        // the form in which serde writes oneofs of the generated types: {"mesh": {"sphere": {..}}}
+        for oneof in &message.oneof_decl {
+            names.push(json_names(oneof.name(), &to_lower_camel_case(oneof.name())));
+            reads.push(format!("crate::json::set_oneof::<A, _>(&mut self.{0}, map.next_value()?, \"{1}.{0}\")?", oneof.name(), name));
+        }
+
+        writeln!(code).unwrap();
+        writeln!(code, "impl crate::json::JsonMessage for {} {{", name).unwrap();
+        writeln!(code, "    const NAME: &'static str = \"{}\";", name).unwrap();
+        writeln!(code, "    const FIELDS: &'static [&'static [&'static str]] = &[{}];", names.join(", ")).unwrap();
+        writeln!(code).unwrap();
+        if reads.is_empty() {
+            writeln!(code, "    fn read_field<'de, A: serde::de::MapAccess<'de>>(&mut self, _field: usize, _map: &mut A) -> Result<(), A::Error> {{").unwrap();
+        } else {
+            writeln!(code, "    fn read_field<'de, A: serde::de::MapAccess<'de>>(&mut self, field: usize, map: &mut A) -> Result<(), A::Error> {{").unwrap();
+            writeln!(code, "        match field {{").unwrap();
+            for (index, read) in reads.iter().enumerate() {
+                writeln!(code, "            {} => {},", index, read).unwrap();
+            }
+            writeln!(code, "            _ => unreachable!(\"field index out of FIELDS range\"),").unwrap();
+            writeln!(code, "        }}").unwrap();
+        }
+        writeln!(code, "        Ok(())").unwrap();
+        writeln!(code, "    }}").unwrap();
+        writeln!(code, "}}").unwrap();
+        writeln!(code).unwrap();
+        writeln!(code, "impl<'de> serde::Deserialize<'de> for {} {{", name).unwrap();
+        writeln!(code, "    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{").unwrap();
+        writeln!(code, "        crate::json::deserialize_message(deserializer)").unwrap();
+        writeln!(code, "    }}").unwrap();
+        writeln!(code, "}}").unwrap();
+    }
+
+    code
+}
+
+fn json_names(name: &str, json_name: &str) -> String {
+    if name == json_name {
+        format!("&[\"{}\"]", name)
+    } else {
+        format!("&[\"{}\", \"{}\"]", name, json_name)
+    }
+}
+
+/// Expression reading a single field value, `null` resulting in `None`.
+fn json_read_optional(field: &FieldDescriptorProto) -> String {
+    match field.r#type() {
+        _ if is_number(field) => {
+            "map.next_value::<Option<crate::json::Number<_>>>()?.map(|number| number.0)".to_string()
+        },
+        Type::String | Type::Bool | Type::Message => "map.next_value::<Option<_>>()?".to_string(),
+        other => panic!("JSON is not supported for fields of type {:?}", other),
+    }
+}
+
+fn json_read_repeated(field: &FieldDescriptorProto) -> String {
+    match field.r#type() {
+        Type::Double | Type::Float | Type::Int32 | Type::Int64 | Type::Uint32 | Type::Uint64 | Type::Sint32 | Type::Sint64 => {
+            "map.next_value::<Option<Vec<crate::json::Number<_>>>>()?.map(|numbers| numbers.into_iter().map(|number| number.0).collect())".to_string()
+        },
+        Type::String | Type::Bool | Type::Message => "map.next_value::<Option<Vec<_>>>()?".to_string(),
+        other => panic!("JSON is not supported for fields of type {:?}", other),
+    }
+}
+
+fn to_lower_camel_case(name: &str) -> String {
+    let upper = to_upper_camel_case(name);
+    let mut chars = upper.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn message_type_name(field: &FieldDescriptorProto) -> &str {
+    field.type_name().rsplit('.').next().unwrap()
+}
+
+fn zero_value(field: &FieldDescriptorProto) -> &'static str {
+    match field.r#type() {
+        Type::Double | Type::Float => "0.0",
+        _ => "0",
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            result.push('_');
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+fn to_upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
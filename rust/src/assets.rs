@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::{env, fs};
+
+use crate::errors::SceneIOError;
+use crate::{scene_object, MeshedObject, Scene};
+
+/// Source of the files referenced from a scene, e.g. obj files of `MeshedObject`.
+pub trait AssetResolver: Send + Sync {
+
+    /// Reads the referenced asset.
+    fn read(&self, reference: &str) -> Result<Vec<u8>, SceneIOError>;
+
+    /// Same as `read`, failing with `SceneIOError::TooLarge` if the asset exceeds the limit.
+    /// The default implementation reads the whole asset first, resolvers should override it to stop early.
+    fn read_limited(&self, reference: &str, limit: Option<u64>) -> Result<Vec<u8>, SceneIOError> {
+        let data = self.read(reference)?;
+        check_asset_size(reference, data.len(), limit)?;
+        Ok(data)
+    }
+
+    /// Path of the referenced asset in the filesystem, if the resolver reads assets from it.
+    fn path(&self, _reference: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Reads assets from the filesystem, relative references are resolved against the root directory.
+#[derive(Clone, Debug)]
+pub struct FileSystemResolver {
+    root: PathBuf,
+}
+
+impl FileSystemResolver {
+
+    pub fn new<P: AsRef<Path>>(root: P) -> FileSystemResolver {
+        FileSystemResolver {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl AssetResolver for FileSystemResolver {
+
+    fn read(&self, reference: &str) -> Result<Vec<u8>, SceneIOError> {
+        self.read_limited(reference, None)
+    }
+
+    fn read_limited(&self, reference: &str, limit: Option<u64>) -> Result<Vec<u8>, SceneIOError> {
+        read_file(&self.root.join(reference), reference, limit)
+    }
+
+    fn path(&self, reference: &str) -> Option<PathBuf> {
+        Some(self.root.join(reference))
+    }
+}
+
+/// Looks for assets in each of the directories in order, the first existing file is used.
+#[derive(Clone, Debug, Default)]
+pub struct SearchPathResolver {
+    directories: Vec<PathBuf>,
+}
+
+impl SearchPathResolver {
+
+    pub fn new<I: IntoIterator<Item = P>, P: AsRef<Path>>(directories: I) -> SearchPathResolver {
+        SearchPathResolver {
+            directories: directories.into_iter().map(|directory| directory.as_ref().to_path_buf()).collect(),
+        }
+    }
+}
+
+impl AssetResolver for SearchPathResolver {
+
+    fn read(&self, reference: &str) -> Result<Vec<u8>, SceneIOError> {
+        self.read_limited(reference, None)
+    }
+
+    fn read_limited(&self, reference: &str, limit: Option<u64>) -> Result<Vec<u8>, SceneIOError> {
+        match self.path(reference) {
+            Some(path) => read_file(&path, reference, limit),
+            None => Err(SceneIOError::AssetNotFound { reference: reference.to_string() }),
+        }
+    }
+
+    fn path(&self, reference: &str) -> Option<PathBuf> {
+        self.directories.iter()
+            .map(|directory| directory.join(reference))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Keeps assets in memory, e.g. for tests or resources embedded into the binary.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryResolver {
+    assets: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryResolver {
+
+    pub fn new() -> MemoryResolver {
+        MemoryResolver::default()
+    }
+
+    pub fn insert<S: Into<String>, D: Into<Vec<u8>>>(&mut self, reference: S, data: D) {
+        self.assets.insert(normalize_reference(&reference.into()).to_string(), data.into());
+    }
+
+    pub fn with<S: Into<String>, D: Into<Vec<u8>>>(mut self, reference: S, data: D) -> MemoryResolver {
+        self.insert(reference, data);
+        self
+    }
+}
+
+impl AssetResolver for MemoryResolver {
+
+    fn read(&self, reference: &str) -> Result<Vec<u8>, SceneIOError> {
+        self.read_limited(reference, None)
+    }
+
+    fn read_limited(&self, reference: &str, limit: Option<u64>) -> Result<Vec<u8>, SceneIOError> {
+        let data = self.assets.get(normalize_reference(reference))
+            .ok_or_else(|| SceneIOError::AssetNotFound { reference: reference.to_string() })?;
+        check_asset_size(reference, data.len(), limit)?;
+        Ok(data.clone())
+    }
+}
+
+/// Assets bundled into a tar archive, compressed with gzip or zstd if the `compression` feature is enabled.
+/// Paths of the archive entries are used as references.
+#[cfg(feature = "archive")]
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveResolver {
+    entries: MemoryResolver,
+}
+
+#[cfg(feature = "archive")]
+impl ArchiveResolver {
+
+    /// Reads a tar archive, compressed or not. Fails with `SceneIOError::TooLarge` if the archive,
+    /// after decompression, is larger than `max_size` bytes.
+    pub fn from_bytes(data: &[u8], max_size: Option<u64>) -> Result<ArchiveResolver, SceneIOError> {
+        use std::io::Read;
+
+        crate::check_size("Archive", data.len(), max_size)?;
+        let data = crate::compression::decompress_named("Decompressed archive", data, max_size)?;
+        let mut archive = tar::Archive::new(data.as_ref());
+        let mut entries = MemoryResolver::new();
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let reference = entry.path()?.to_string_lossy().into_owned();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            entries.insert(reference, content);
+        }
+
+        Ok(ArchiveResolver {
+            entries,
+        })
+    }
+
+    /// Same as `from_bytes`, for the archive file.
+    pub fn open<P: AsRef<Path>>(path: P, max_size: Option<u64>) -> Result<ArchiveResolver, SceneIOError> {
+        ArchiveResolver::from_bytes(&crate::read_limited(fs::File::open(path)?, max_size)?, max_size)
+    }
+}
+
+#[cfg(feature = "archive")]
+impl AssetResolver for ArchiveResolver {
+
+    fn read(&self, reference: &str) -> Result<Vec<u8>, SceneIOError> {
+        self.entries.read(reference)
+    }
+
+    fn read_limited(&self, reference: &str, limit: Option<u64>) -> Result<Vec<u8>, SceneIOError> {
+        self.entries.read_limited(reference, limit)
+    }
+}
+
+fn read_file(path: &Path, reference: &str, limit: Option<u64>) -> Result<Vec<u8>, SceneIOError> {
+    let data = fs::File::open(path)
+        .and_then(|file| crate::read_limited(file, limit))
+        .map_err(|source| SceneIOError::FailedToReadAsset {
+            reference: reference.to_string(),
+            source,
+        })?;
+    check_asset_size(reference, data.len(), limit)?;
+    Ok(data)
+}
+
+fn check_asset_size(reference: &str, size: usize, limit: Option<u64>) -> Result<(), SceneIOError> {
+    crate::check_size(&format!("Asset \"{}\"", reference), size, limit)
+}
+
+fn normalize_reference(reference: &str) -> &str {
+    reference.trim_start_matches("./")
+}
+
+impl MeshedObject {
+
+    /// Absolute path of the referenced obj file, relative references are resolved against the directory of the scene.
+    pub fn resolved_path(&self, scene_directory: &Path) -> Option<PathBuf> {
+        if self.reference.is_empty() {
+            return None;
+        }
+
+        Some(normalize_path(&absolute_path(&scene_directory.join(&self.reference))))
+    }
+}
+
+/// Rewrites relative references of meshed objects, so that they point to the same files
+/// when the scene is moved from one directory to the other. Absolute references are kept as is.
+pub fn rebase_references(scene: &mut Scene, from_directory: &Path, to_directory: &Path) {
+    let to_directory = normalize_path(&absolute_path(to_directory));
+
+    for meshed_object in meshed_objects(scene) {
+        if Path::new(&meshed_object.reference).is_absolute() {
+            continue;
+        }
+
+        if let Some(path) = meshed_object.resolved_path(from_directory) {
+            meshed_object.reference = match relative_path(&path, &to_directory) {
+                Some(relative) => relative,
+                None => path.to_string_lossy().into_owned(),
+            };
+        }
+    }
+}
+
+/// Rewrites relative references of meshed objects to absolute paths resolved against the directory of the scene,
+/// so that they point to the same files wherever the scene is saved.
+pub fn resolve_references(scene: &mut Scene, scene_directory: &Path) {
+    for meshed_object in meshed_objects(scene) {
+        if Path::new(&meshed_object.reference).is_absolute() {
+            continue;
+        }
+
+        if let Some(path) = meshed_object.resolved_path(scene_directory) {
+            meshed_object.reference = path.to_string_lossy().into_owned();
+        }
+    }
+}
+
+/// Rewrites absolute references of meshed objects to be relative to the directory.
+/// References on another drive than the directory stay absolute.
+pub fn relativize_references(scene: &mut Scene, directory: &Path) {
+    let directory = normalize_path(&absolute_path(directory));
+
+    for meshed_object in meshed_objects(scene) {
+        let path = Path::new(&meshed_object.reference);
+        if !path.is_absolute() {
+            continue;
+        }
+
+        if let Some(relative) = relative_path(&normalize_path(path), &directory) {
+            meshed_object.reference = relative;
+        }
+    }
+}
+
+pub(crate) fn has_absolute_references(scene: &Scene) -> bool {
+    scene.scene_objects.iter().any(|scene_object| match &scene_object.mesh {
+        Some(scene_object::Mesh::MeshedObject(meshed_object)) => Path::new(&meshed_object.reference).is_absolute(),
+        _ => false,
+    })
+}
+
+fn meshed_objects(scene: &mut Scene) -> impl Iterator<Item = &mut MeshedObject> {
+    scene.scene_objects.iter_mut().filter_map(|scene_object| match &mut scene_object.mesh {
+        Some(scene_object::Mesh::MeshedObject(meshed_object)) => Some(meshed_object),
+        _ => None,
+    })
+}
+
+fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    match env::current_dir() {
+        Ok(current_dir) => current_dir.join(path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Removes `.` and `..` components without touching the filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            },
+            other => normalized.push(other),
+        }
+    }
+
+    normalized
+}
+
+/// Path from the directory to the target, with `/` as separator as references are shared between platforms.
+/// Both paths should be absolute and normalized, there is no relative path between different drives.
+fn relative_path(target: &Path, directory: &Path) -> Option<String> {
+    let target: Vec<_> = target.components().collect();
+    let directory: Vec<_> = directory.components().collect();
+
+    let common = target.iter().zip(&directory).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+
+    let parts: Vec<String> = directory[common..].iter().map(|_| "..".to_string())
+        .chain(target[common..].iter().map(|component| component.as_os_str().to_string_lossy().into_owned()))
+        .collect();
+
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn file_system_resolver() {
+        let resolver = FileSystemResolver::new("./examples");
+
+        assert_eq!(fs::read("./examples/assets/cow.obj").unwrap(), resolver.read("assets/cow.obj").unwrap());
+        assert_eq!(Some(PathBuf::from("./examples/assets/cow.obj")), resolver.path("assets/cow.obj"));
+        assert!(matches!(resolver.read("assets/missing.obj"), Err(SceneIOError::FailedToReadAsset { .. })));
+    }
+
+    #[test]
+    fn search_path_resolver() {
+        let resolver = SearchPathResolver::new(["./src", "./examples/assets"]);
+
+        assert_eq!(Some(PathBuf::from("./examples/assets/cow.obj")), resolver.path("cow.obj"));
+        assert!(resolver.read("cow.obj").is_ok());
+        assert_eq!("Asset \"missing.obj\" is not found", resolver.read("missing.obj").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn memory_resolver() {
+        let resolver = MemoryResolver::new().with("assets/triangle.obj", "v 0 0 0");
+
+        assert_eq!(b"v 0 0 0".to_vec(), resolver.read("./assets/triangle.obj").unwrap());
+        assert_eq!(None, resolver.path("assets/triangle.obj"));
+        assert!(resolver.read("triangle.obj").is_err());
+    }
+
+    #[test]
+    fn size_limits() {
+        let size = fs::metadata("./examples/assets/cow.obj").unwrap().len();
+        let file_system = FileSystemResolver::new("./examples");
+        let memory = MemoryResolver::new().with("assets/cow.obj", fs::read("./examples/assets/cow.obj").unwrap());
+
+        for resolver in &[&file_system as &dyn AssetResolver, &memory] {
+            assert!(resolver.read_limited("assets/cow.obj", Some(size)).is_ok());
+            assert_eq!("Asset \"assets/cow.obj\" is larger than the limit of 1024 bytes",
+                resolver.read_limited("assets/cow.obj", Some(1024)).unwrap_err().to_string());
+        }
+    }
+
+    #[test]
+    fn resolved_path() {
+        let meshed_object = MeshedObject {
+            reference: "../assets/./cow.obj".to_string(),
+            obj: None,
+        };
+
+        assert_eq!(Some(env::current_dir().unwrap().join("examples/assets/cow.obj")), meshed_object.resolved_path(Path::new("examples/scenes")));
+        assert_eq!(None, MeshedObject::default().resolved_path(Path::new("examples")));
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(Some("../assets/cow.obj".to_string()), relative_path(Path::new("/scenes/assets/cow.obj"), Path::new("/scenes/out")));
+        assert_eq!(Some("cow.obj".to_string()), relative_path(Path::new("/scenes/cow.obj"), Path::new("/scenes")));
+        assert_eq!(PathBuf::from("/scenes/cow.obj"), normalize_path(Path::new("/scenes/./out/../cow.obj")));
+    }
+
+    #[test]
+    fn rebased_references() {
+        // without the directory of the scene references are kept as written
+        let mut scene = crate::read_from(fs::File::open("./examples/5.cowscene").unwrap(), None).unwrap();
+
+        rebase_references(&mut scene, Path::new("./examples"), Path::new("./examples/output"));
+
+        match &scene.scene_objects[0].mesh {
+            Some(scene_object::Mesh::MeshedObject(meshed_object)) => assert_eq!("../assets/cow.obj", meshed_object.reference),
+            other => panic!("unexpected mesh: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolved_and_relativized_references() {
+        let mut scene = crate::read_from(fs::File::open("./examples/5.cowscene").unwrap(), None).unwrap();
+
+        resolve_references(&mut scene, Path::new("/scenes"));
+        assert!(has_absolute_references(&scene));
+        relativize_references(&mut scene, Path::new("/scenes/output"));
+
+        match &scene.scene_objects[0].mesh {
+            Some(scene_object::Mesh::MeshedObject(meshed_object)) => assert_eq!("../assets/cow.obj", meshed_object.reference),
+            other => panic!("unexpected mesh: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn archive_resolver() {
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append_path_with_name("./examples/assets/cow.obj", "assets/cow.obj").unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let resolver = ArchiveResolver::from_bytes(&archive, None).unwrap();
+
+        assert_eq!(fs::read("./examples/assets/cow.obj").unwrap(), resolver.read("assets/cow.obj").unwrap());
+        assert!(resolver.read("cow.obj").is_err());
+        assert_eq!("Archive is larger than the limit of 1024 bytes",
+            ArchiveResolver::from_bytes(&archive, Some(1024)).unwrap_err().to_string());
+    }
+
+    #[cfg(all(feature = "archive", feature = "compression"))]
+    #[test]
+    fn compressed_archive_size_limit() {
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append_path_with_name("./examples/assets/cow.obj", "assets/cow.obj").unwrap();
+        let archive = builder.into_inner().unwrap();
+        let compressed = crate::compression::compress(archive.clone(), crate::compression::Compression::Gzip).unwrap();
+        let limit = compressed.len() as u64;
+
+        assert_eq!(format!("Decompressed archive is larger than the limit of {} bytes", limit),
+            ArchiveResolver::from_bytes(&compressed, Some(limit)).unwrap_err().to_string());
+        assert!(ArchiveResolver::from_bytes(&compressed, Some(archive.len() as u64)).is_ok());
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::errors::SceneIOError;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes data to a temporary file in the same directory and renames it into place,
+/// so that readers see either the old or the new content of the file, but never a part of it.
+/// With `sync`, the data and the rename are flushed to disk before returning.
+pub fn write_atomically(path: &Path, data: &[u8], sync: bool) -> Result<(), SceneIOError> {
+    let temp_path = temp_path(path);
+
+    let result = write_temp_file(&temp_path, data, sync).and_then(|_| fs::rename(&temp_path, path));
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err.into());
+    }
+
+    if sync {
+        sync_directory(&directory(path))?;
+    }
+
+    Ok(())
+}
+
+fn write_temp_file(temp_path: &Path, data: &[u8], sync: bool) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(temp_path)?;
+    file.write_all(data)?;
+    if sync {
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+fn directory(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let counter = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    directory(path).join(format!(".{}.{}-{}.tmp", file_name, std::process::id(), counter))
+}
+
+#[cfg(unix)]
+fn sync_directory(directory: &Path) -> Result<(), SceneIOError> {
+    File::open(directory)?.sync_all()?;
+    Ok(())
+}
+
+// directories can not be opened as files on other platforms, the rename is durable once it returns
+#[cfg(not(unix))]
+fn sync_directory(_directory: &Path) -> Result<(), SceneIOError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("sceneformat_atomic_{}", name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn replaces_file_without_leaving_temp_files() {
+        let directory = test_directory("replace");
+        let path = directory.join("scene.cowscene");
+        fs::write(&path, b"old content").unwrap();
+
+        write_atomically(&path, b"new", false).unwrap();
+        write_atomically(&path, b"newer", true).unwrap();
+
+        assert_eq!(b"newer".to_vec(), fs::read(&path).unwrap());
+        assert_eq!(1, fs::read_dir(&directory).unwrap().count());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn failed_write_keeps_old_file() {
+        let directory = test_directory("failed");
+        // renaming a file over a non-empty directory fails
+        let path = directory.join("scene.cowscene");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("keep"), b"old").unwrap();
+
+        assert!(write_atomically(&path, b"new", false).is_err());
+
+        assert_eq!(b"old".to_vec(), fs::read(path.join("keep")).unwrap());
+        assert_eq!(1, fs::read_dir(&directory).unwrap().count());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::path::Path;
+
+use crate::errors::SceneIOError;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of a whole scene file, JSON or binary.
+/// Compressed files can only be written and read with the `compression` feature enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+
+    /// Compression implied by the extension of a file, e.g. `scene.cowscene.gz` or `scene.cowscene.zst`.
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Compression detected from the magic bytes at the start of the data.
+    pub fn detect(data: &[u8]) -> Compression {
+        if data.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if data.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+pub fn compress(data: Vec<u8>, compression: Compression) -> Result<Vec<u8>, SceneIOError> {
+    match compression {
+        Compression::None => Ok(data),
+        #[cfg(feature = "compression")]
+        Compression::Gzip => {
+            use std::io::Write;
+
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            Ok(encoder.finish()?)
+        },
+        #[cfg(feature = "compression")]
+        Compression::Zstd => Ok(zstd::stream::encode_all(&data[..], 0)?),
+        #[cfg(not(feature = "compression"))]
+        compression => Err(SceneIOError::CompressionNotEnabled { compression }),
+    }
+}
+
+/// Decompresses the data if it starts with gzip or zstd magic bytes, otherwise returns it as is.
+pub fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, SceneIOError> {
+    decompress_limited(data, None)
+}
+
+/// Same as `decompress`, stops with `SceneIOError::TooLarge` as soon as the decompressed data exceeds the limit.
+pub fn decompress_limited(data: &[u8], limit: Option<u64>) -> Result<Cow<'_, [u8]>, SceneIOError> {
+    decompress_named("Decompressed scene", data, limit)
+}
+
+/// Same as `decompress_limited`, with the name of the data in `SceneIOError::TooLarge`.
+#[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+pub(crate) fn decompress_named<'a>(name: &str, data: &'a [u8], limit: Option<u64>) -> Result<Cow<'a, [u8]>, SceneIOError> {
+    match Compression::detect(data) {
+        Compression::None => Ok(Cow::Borrowed(data)),
+        #[cfg(feature = "compression")]
+        Compression::Gzip => read_decompressed(name, flate2::read::MultiGzDecoder::new(data), limit),
+        #[cfg(feature = "compression")]
+        Compression::Zstd => read_decompressed(name, zstd::stream::read::Decoder::new(data)?, limit),
+        #[cfg(not(feature = "compression"))]
+        compression => Err(SceneIOError::CompressionNotEnabled { compression }),
+    }
+}
+
+#[cfg(feature = "compression")]
+fn read_decompressed<R: std::io::Read>(name: &str, decoder: R, limit: Option<u64>) -> Result<Cow<'static, [u8]>, SceneIOError> {
+    let decompressed = crate::read_limited(decoder, limit)?;
+    crate::check_size(name, decompressed.len(), limit)?;
+    Ok(Cow::Owned(decompressed))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn compression_from_path_and_content() {
+        assert_eq!(Compression::Gzip, Compression::from_path(Path::new("scene.cowscene.gz")));
+        assert_eq!(Compression::Zstd, Compression::from_path(Path::new("scene.cowscene.zst")));
+        assert_eq!(Compression::None, Compression::from_path(Path::new("scene.cowscene")));
+
+        assert_eq!(Compression::Gzip, Compression::detect(&[0x1f, 0x8b, 0x08]));
+        assert_eq!(Compression::Zstd, Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]));
+        assert_eq!(Compression::None, Compression::detect(b"{}"));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn round_trip() {
+        let data = std::fs::read("./examples/5.cowscene").unwrap();
+
+        for compression in &[Compression::None, Compression::Gzip, Compression::Zstd] {
+            let compressed = compress(data.clone(), *compression).unwrap();
+            assert_eq!(*compression, Compression::detect(&compressed));
+            assert_eq!(data, decompress(&compressed).unwrap().into_owned());
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decompression_limit() {
+        let bomb = compress(vec![b' '; 1 << 20], Compression::Gzip).unwrap();
+
+        assert_eq!("Decompressed scene is larger than the limit of 1024 bytes",
+            decompress_limited(&bomb, Some(1024)).unwrap_err().to_string());
+        assert_eq!(1 << 20, decompress_limited(&bomb, Some(1 << 20)).unwrap().len());
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn compression_requires_feature() {
+        assert_eq!("Scene is compressed with gzip, but sceneformat is built without the \"compression\" feature",
+            decompress(&[0x1f, 0x8b, 0x08]).unwrap_err().to_string());
+        assert!(compress(Vec::new(), Compression::Zstd).is_err());
+    }
+}
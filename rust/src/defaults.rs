@@ -0,0 +1,124 @@
+use crate::Scene;
+
+/// Fills in the values documented with `@default` in scene.proto for fields left unset.
+///
+/// The per-message `apply_defaults` implementations are generated by build.rs from the
+/// annotations in scene.proto, so this stays in sync with the spec.
+pub fn apply_defaults(scene: &mut Scene) {
+    scene.apply_defaults();
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{
+        camera, light, material, scene_object, Camera, Color, Cube, Light, Material,
+        PerspectiveCamera, RenderOptions, SceneObject, Sphere, SpecularReflectionMaterial,
+        Transform, Vector3,
+    };
+
+    fn vector3(x: f64, y: f64, z: f64) -> Vector3 {
+        Vector3 {
+            x,
+            y,
+            z,
+        }
+    }
+
+    #[test]
+    fn fills_documented_defaults() {
+        let mut scene = Scene {
+            version: 0,
+            render_options: Some(RenderOptions::default()),
+            scene_objects: vec![
+                SceneObject {
+                    id: 2,
+                    transform: None,
+                    object_material: Some(scene_object::ObjectMaterial::Material(Material {
+                        id: "".to_string(),
+                        material: Some(material::Material::SpecularReflection(SpecularReflectionMaterial {
+                            eta: 0.0,
+                        })),
+                    })),
+                    mesh: Some(scene_object::Mesh::Sphere(Sphere {
+                        radius: 0.0,
+                    })),
+                },
+                SceneObject {
+                    id: 3,
+                    transform: None,
+                    object_material: None,
+                    mesh: Some(scene_object::Mesh::Cube(Cube {
+                        size: None,
+                    })),
+                },
+            ],
+            lights: vec![
+                Light {
+                    id: 4,
+                    transform: None,
+                    color: None,
+                    light: Some(light::Light::Point(crate::LightPoint {})),
+                },
+            ],
+            cameras: vec![
+                Camera {
+                    id: 1,
+                    transform: None,
+                    camera: Some(camera::Camera::Perspective(PerspectiveCamera {
+                        fov: 0.0,
+                    })),
+                },
+            ],
+            materials: vec![],
+        };
+
+        apply_defaults(&mut scene);
+
+        assert_eq!(1, scene.version);
+        assert_eq!(Some(RenderOptions {
+            camera_id: 1,
+            width: 640,
+            height: 320,
+            custom_properties: Vec::new(),
+        }), scene.render_options);
+        assert_eq!(Some(Transform {
+            parent_id: 0,
+            position: Some(vector3(0.0, 0.0, 0.0)),
+            rotation: Some(vector3(0.0, 0.0, 0.0)),
+            scale: Some(vector3(1.0, 1.0, 1.0)),
+        }), scene.scene_objects[0].transform);
+        assert_eq!(Some(scene_object::Mesh::Sphere(Sphere {
+            radius: 1.0,
+        })), scene.scene_objects[0].mesh);
+        assert_eq!(Some(scene_object::ObjectMaterial::Material(Material {
+            id: "".to_string(),
+            material: Some(material::Material::SpecularReflection(SpecularReflectionMaterial {
+                eta: 1.5,
+            })),
+        })), scene.scene_objects[0].object_material);
+        assert_eq!(Some(scene_object::Mesh::Cube(Cube {
+            size: Some(vector3(1.0, 1.0, 1.0)),
+        })), scene.scene_objects[1].mesh);
+        assert_eq!(Some(Color {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+        }), scene.lights[0].color);
+        assert_eq!(Some(camera::Camera::Perspective(PerspectiveCamera {
+            fov: 60.0,
+        })), scene.cameras[0].camera);
+    }
+
+    #[test]
+    fn keeps_explicit_values() {
+        let mut scene = crate::read("./examples/7.cowscene").unwrap();
+        let before = scene.clone();
+
+        apply_defaults(&mut scene);
+
+        assert_eq!(before, scene);
+        assert_eq!(Some(vector3(0.4, 1.0, 4.0)), scene.scene_objects[0].transform.as_ref().unwrap().scale);
+    }
+}
@@ -0,0 +1,77 @@
+/// Can not be the start of a plain protobuf `Scene`, as `C` would be a group with field number 8.
+pub const MAGIC: &[u8] = b"COWSCENE";
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Optional header of binary scene files, followed by the `Scene` protobuf message.
+/// Files without the header are read as plain protobuf.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    /// Reserved for compression of the payload, no flags are defined in version 1.
+    pub flags: u8,
+}
+
+pub fn has_header(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn write_header(buf: &mut Vec<u8>, header: Header) {
+    buf.extend_from_slice(MAGIC);
+    buf.push(header.version);
+    buf.push(header.flags);
+}
+
+/// Splits enveloped data into header and payload. Data without the header is returned as is.
+pub fn split(data: &[u8]) -> Result<(Option<Header>, &[u8]), String> {
+    if !has_header(data) {
+        return Ok((None, data));
+    }
+
+    if data.len() < HEADER_LEN {
+        return Err("binary scene header is truncated".to_string());
+    }
+
+    let header = Header {
+        version: data[MAGIC.len()],
+        flags: data[MAGIC.len() + 1],
+    };
+
+    if header.version != VERSION {
+        return Err(format!("unsupported binary scene envelope version = {}", header.version));
+    }
+
+    if header.flags != 0 {
+        return Err(format!("unsupported binary scene envelope flags = {:#04x}", header.flags));
+    }
+
+    Ok((Some(header), &data[HEADER_LEN..]))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn header_round_trip() {
+        let header = Header { version: VERSION, flags: 0 };
+        let mut data = Vec::new();
+        write_header(&mut data, header);
+        data.extend_from_slice(&[8, 1]);
+
+        assert_eq!(Ok((Some(header), &[8u8, 1][..])), split(&data));
+    }
+
+    #[test]
+    fn legacy_data_is_payload() {
+        assert_eq!(Ok((None, &[8u8, 1][..])), split(&[8, 1]));
+    }
+
+    #[test]
+    fn invalid_headers() {
+        assert_eq!(Err("binary scene header is truncated".to_string()), split(b"COWSCENE\x01"));
+        assert_eq!(Err("unsupported binary scene envelope version = 2".to_string()), split(b"COWSCENE\x02\x00"));
+        assert_eq!(Err("unsupported binary scene envelope flags = 0x80".to_string()), split(b"COWSCENE\x01\x80"));
+    }
+}
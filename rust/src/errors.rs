@@ -1,8 +1,108 @@
+use std::path::PathBuf;
+
 use custom_error::custom_error;
 
+use crate::compression::Compression;
+
 custom_error!{pub SceneIOError
     FailedToEncode{description: String} = "Failed to encode: {description}",
     FailedToDecode{description: String} = "Failed to decode: {description}",
+    UnknownFormat{json_error: String, binary_error: String} = "Failed to decode as json: {json_error}; failed to decode as binary: {binary_error}",
     IOError {source: std::io::Error} = "IO Error: {source}",
-    FailedToReadObj{description: String} = "Failed to read obj file: {description}",
+    FailedToReadObj{source: ObjError} = "Failed to read obj file: {source}",
+    AssetNotFound{reference: String} = "Asset \"{reference}\" is not found",
+    FailedToReadAsset{reference: String, source: std::io::Error} = "Failed to read asset \"{reference}\": {source}",
+    NoAssetResolver = "Meshes can not be loaded without an asset resolver",
+    TooLarge{name: String, limit: u64} = "{name} is larger than the limit of {limit} bytes",
+    CompressionNotEnabled{compression: Compression} = "Scene is compressed with {compression}, but sceneformat is built without the \"compression\" feature",
+    InvalidScene{errors: Vec<ValidationError>} = @{
+        format!("Invalid scene: {}", errors.iter().map(|err| err.to_string()).collect::<Vec<_>>().join("; "))
+    },
+}
+
+custom_error!{#[derive(PartialEq)] pub ValidationError
+    UnsupportedVersion{version: i32} = "Unsupported scene format version = {version}",
+    InvalidId{kind: &'static str, id: i32} = "{kind} id should be equal or greater than 1, instead got: {id}",
+    DuplicateId{kind: &'static str, id: i32} = "Found {kind} with id = {id}, this id is already used by another camera, light or scene object",
+    DuplicateMaterialId{material_id: String} = "Materials with duplicate id found: \"{material_id}\"",
+    UnknownCamera{camera_id: i32} = "RenderOptions references camera with id = {camera_id} which is not present on the scene",
+    InvalidImageSize{width: i32, height: i32} = "RenderOptions width and height should be positive, got {width}x{height}",
+    UnknownMaterial{object_id: i32, material_id: String} = "Material with id = \"{material_id}\" is not present, but referenced by scene object with id = {object_id}",
+    UnknownParent{kind: &'static str, id: i32, parent_id: i32} = "Could not find parent transform with id = {parent_id} for {kind} with id = {id}",
+    ParentCycle{members: Vec<i32>} = @{
+        format!("Transform.parent_id forms a cycle: {}", members.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" -> "))
+    },
+    NegativeScale{kind: &'static str, id: i32} = "Transform scale should be non-negative, {kind} with id = {id}",
+    UnexpectedScale{kind: &'static str, id: i32} = "Scale cannot be applied to {kind}, but {kind} with id = {id} does have it",
+    InvalidRadius{kind: &'static str, id: i32} = @{
+        format!("{} should have a positive radius, {} id = {}", kind, radius_owner(kind), id)
+    },
+    NegativeCubeSize{id: i32} = "Cube size should be non-negative, scene object id = {id}",
+    MissingReference{id: i32} = "Reference not set for meshed object with id = {id}",
+    InvalidFov{id: i32, fov: f64} = "Invalid FOV = {fov} for camera with id = {id}, valid values are in range (0, 180)",
+    MissingField{path: String} = "Required field is not set: {path}",
+    NegativeColor{owner: String} = "Color channels should be non-negative, {owner}",
+}
+
+/// Light spheres belong to lights, the other shapes with radius to scene objects.
+fn radius_owner(kind: &str) -> &'static str {
+    match kind {
+        "LightSphere" => "light",
+        _ => "scene object",
+    }
+}
+
+custom_error!{#[derive(PartialEq)] pub HierarchyError
+    DuplicateId{id: i32} = "Found several objects with id = {id}",
+    UnknownParent{id: i32, parent_id: i32} = "Could not find parent transform with id = {parent_id} for object with id = {id}",
+    UnknownId{id: i32} = "Object with id = {id} from the hierarchy is not in the scene",
+    ParentCycle{members: Vec<i32>} = @{
+        format!("Transform.parent_id forms a cycle: {}", members.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" -> "))
+    },
+}
+
+custom_error!{#[derive(PartialEq)] pub MaterialError
+    NotSet{object_id: i32} = "Material is not set for scene object with id = {object_id}",
+    UnknownMaterial{object_id: i32, material_id: String} = "Material with id = \"{material_id}\" is not present, but referenced by scene object with id = {object_id}",
+}
+
+custom_error!{pub ObjError{
+    /// File the obj is read from, if it is known.
+    path: Option<PathBuf>,
+    /// 1-based, 0 if the error is not related to a line.
+    line: usize,
+    /// 1-based, 0 if the error is not related to a part of the line.
+    column: usize,
+    /// Offending line, comments and continuations removed.
+    text: String,
+    source: ObjErrorKind
+} = @{
+    obj_error_message(path, *line, *column, text, source)
+}}
+
+fn obj_error_message(path: &Option<PathBuf>, line: usize, column: usize, text: &str, kind: &ObjErrorKind) -> String {
+    let mut location = Vec::new();
+    if let Some(path) = path {
+        location.push(path.display().to_string());
+    }
+    if line > 0 {
+        location.push(format!("line {}", line));
+    }
+    if column > 0 {
+        location.push(format!("column {}", column));
+    }
+
+    match (location.is_empty(), text.is_empty()) {
+        (true, _) => kind.to_string(),
+        (false, true) => format!("{}: {}", location.join(", "), kind),
+        (false, false) => format!("{}: {} in \"{}\"", location.join(", "), kind, text),
+    }
+}
+
+custom_error!{pub ObjErrorKind
+    BadNumber{name: &'static str, description: String} = "Failed to parse {name}: {description}",
+    TooFewComponents{expected: String} = "Expected {expected}",
+    InvalidFaceElement{element: String} = "Face element should be one of v, v/vt, v//vn and v/vt/vn, got \"{element}\"",
+    IndexOutOfRange{name: &'static str, index: i32, count: usize} = "Face element {name} {index} is out of range, there are {count} defined before it",
+    IOError{source: std::io::Error} = "IO Error: {source}",
 }
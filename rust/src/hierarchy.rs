@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::HierarchyError;
+use crate::{Scene, Transform};
+
+/// Tree of SceneObjects, Lights and Cameras built from `Transform.parent_id`.
+#[derive(Clone, Debug, Default)]
+pub struct Hierarchy {
+    parents: HashMap<i32, i32>,
+    children: HashMap<i32, Vec<i32>>,
+    roots: Vec<i32>,
+    order: Vec<i32>,
+}
+
+impl Hierarchy {
+
+    /// Builds the tree, failing if ids are not unique, some parent does not exist or parent chains form a cycle.
+    pub fn new(scene: &Scene) -> Result<Self, HierarchyError> {
+        let entities = entity_parents(scene);
+        let mut ids: HashSet<i32> = HashSet::new();
+        for &(id, _) in &entities {
+            if !ids.insert(id) {
+                return Err(HierarchyError::DuplicateId { id });
+            }
+        }
+
+        let mut hierarchy = Hierarchy::default();
+        for &(id, parent_id) in &entities {
+            if parent_id == 0 {
+                hierarchy.roots.push(id);
+                continue;
+            }
+
+            if !ids.contains(&parent_id) {
+                return Err(HierarchyError::UnknownParent { id, parent_id });
+            }
+
+            hierarchy.parents.insert(id, parent_id);
+            hierarchy.children.entry(parent_id).or_insert_with(Vec::new).push(id);
+        }
+
+        let mut stack: Vec<i32> = hierarchy.roots.iter().rev().cloned().collect();
+        while let Some(id) = stack.pop() {
+            hierarchy.order.push(id);
+            if let Some(children) = hierarchy.children.get(&id) {
+                stack.extend(children.iter().rev());
+            }
+        }
+
+        if hierarchy.order.len() < entities.len() {
+            let visited: HashSet<i32> = hierarchy.order.iter().cloned().collect();
+            let unvisited = entities.iter().map(|(id, _)| *id).find(|id| !visited.contains(id)).unwrap();
+            return Err(HierarchyError::ParentCycle { members: hierarchy.find_cycle(unvisited) });
+        }
+
+        Ok(hierarchy)
+    }
+
+    /// Follows parents from an entity which is not reachable from any root until an id repeats.
+    fn find_cycle(&self, start: i32) -> Vec<i32> {
+        let mut chain = vec![start];
+        let mut current = start;
+
+        loop {
+            current = self.parents[&current];
+            if let Some(position) = chain.iter().position(|id| *id == current) {
+                return chain.split_off(position);
+            }
+            chain.push(current);
+        }
+    }
+
+    pub fn parent(&self, id: i32) -> Option<i32> {
+        self.parents.get(&id).cloned()
+    }
+
+    pub fn children(&self, id: i32) -> &[i32] {
+        self.children.get(&id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Entities with transforms specified in world space.
+    pub fn roots(&self) -> &[i32] {
+        &self.roots
+    }
+
+    /// All entity ids, every parent preceding its children.
+    pub fn traversal_order(&self) -> &[i32] {
+        &self.order
+    }
+}
+
+/// Ids and parent ids of all SceneObjects, Lights and Cameras in declaration order.
+fn entity_parents(scene: &Scene) -> Vec<(i32, i32)> {
+    let parent_id = |transform: Option<&Transform>| transform.map(|transform| transform.parent_id).unwrap_or(0);
+
+    scene.scene_objects.iter().map(|o| (o.id, parent_id(o.transform.as_ref())))
+        .chain(scene.lights.iter().map(|l| (l.id, parent_id(l.transform.as_ref()))))
+        .chain(scene.cameras.iter().map(|c| (c.id, parent_id(c.transform.as_ref()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn set_parent(scene: &mut Scene, id: i32, parent_id: i32) {
+        let transform = if let Some(scene_object) = scene.scene_objects.iter_mut().find(|o| o.id == id) {
+            &mut scene_object.transform
+        } else if let Some(light) = scene.lights.iter_mut().find(|l| l.id == id) {
+            &mut light.transform
+        } else {
+            &mut scene.cameras.iter_mut().find(|c| c.id == id).unwrap().transform
+        };
+        transform.get_or_insert_with(Transform::default).parent_id = parent_id;
+    }
+
+    #[test]
+    fn traversal_order_puts_parents_first() {
+        let mut scene = crate::read("./examples/11.cowscene").unwrap();
+        set_parent(&mut scene, 2, 3);
+        set_parent(&mut scene, 3, 1);
+        set_parent(&mut scene, 5, 2);
+
+        let hierarchy = Hierarchy::new(&scene).unwrap();
+
+        assert_eq!(&[4, 1], hierarchy.roots());
+        assert_eq!(&[4, 1, 3, 2, 5], hierarchy.traversal_order());
+        assert_eq!(Some(3), hierarchy.parent(2));
+        assert_eq!(None, hierarchy.parent(1));
+        assert_eq!(&[2], hierarchy.children(3));
+    }
+
+    #[test]
+    fn cycle_is_reported() {
+        let mut scene = crate::read("./examples/11.cowscene").unwrap();
+        set_parent(&mut scene, 2, 3);
+        set_parent(&mut scene, 3, 2);
+        set_parent(&mut scene, 5, 2);
+
+        assert_eq!(HierarchyError::ParentCycle { members: vec![2, 3] }, Hierarchy::new(&scene).unwrap_err());
+    }
+
+    #[test]
+    fn self_parent_is_a_cycle() {
+        let mut scene = crate::read("./examples/11.cowscene").unwrap();
+        set_parent(&mut scene, 4, 4);
+
+        assert_eq!(HierarchyError::ParentCycle { members: vec![4] }, Hierarchy::new(&scene).unwrap_err());
+    }
+
+    #[test]
+    fn unknown_parent_is_reported() {
+        let mut scene = crate::read("./examples/11.cowscene").unwrap();
+        set_parent(&mut scene, 3, 42);
+
+        assert_eq!(HierarchyError::UnknownParent { id: 3, parent_id: 42 }, Hierarchy::new(&scene).unwrap_err());
+    }
+
+    #[test]
+    fn duplicate_id_is_reported() {
+        let mut scene = crate::read("./examples/11.cowscene").unwrap();
+        scene.scene_objects[1].id = 2;
+        scene.scene_objects[1].transform.as_mut().unwrap().parent_id = 5;
+        set_parent(&mut scene, 5, 2);
+
+        assert_eq!(HierarchyError::DuplicateId { id: 2 }, Hierarchy::new(&scene).unwrap_err());
+    }
+}
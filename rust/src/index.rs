@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::{Camera, Light, Material, Scene, SceneObject, Transform};
+
+/// Any of the scene entities which have an id unique across the scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Entity<'a> {
+    SceneObject(&'a SceneObject),
+    Light(&'a Light),
+    Camera(&'a Camera),
+}
+
+impl<'a> Entity<'a> {
+
+    pub fn id(&self) -> i32 {
+        match self {
+            Entity::SceneObject(scene_object) => scene_object.id,
+            Entity::Light(light) => light.id,
+            Entity::Camera(camera) => camera.id,
+        }
+    }
+
+    pub fn transform(&self) -> Option<&'a Transform> {
+        match self {
+            Entity::SceneObject(scene_object) => scene_object.transform.as_ref(),
+            Entity::Light(light) => light.transform.as_ref(),
+            Entity::Camera(camera) => camera.transform.as_ref(),
+        }
+    }
+
+    pub fn parent_id(&self) -> i32 {
+        self.transform().map(|transform| transform.parent_id).unwrap_or(0)
+    }
+}
+
+/// Lookup of scene entities and materials by id.
+/// If ids are duplicated, the first declared entity wins.
+#[derive(Clone, Debug, Default)]
+pub struct SceneIndex<'a> {
+    entities: HashMap<i32, Entity<'a>>,
+    materials: HashMap<&'a str, &'a Material>,
+    children: HashMap<i32, Vec<Entity<'a>>>,
+}
+
+impl<'a> SceneIndex<'a> {
+
+    pub fn new(scene: &'a Scene) -> Self {
+        let mut index = SceneIndex::default();
+
+        let entities = scene.scene_objects.iter().map(Entity::SceneObject)
+            .chain(scene.lights.iter().map(Entity::Light))
+            .chain(scene.cameras.iter().map(Entity::Camera));
+
+        for entity in entities {
+            index.entities.entry(entity.id()).or_insert(entity);
+
+            let parent_id = entity.parent_id();
+            if parent_id != 0 {
+                index.children.entry(parent_id).or_insert_with(Vec::new).push(entity);
+            }
+        }
+
+        for material in &scene.materials {
+            index.materials.entry(material.id.as_str()).or_insert(material);
+        }
+
+        index
+    }
+
+    pub fn entity(&self, id: i32) -> Option<Entity<'a>> {
+        self.entities.get(&id).cloned()
+    }
+
+    pub fn camera(&self, id: i32) -> Option<&'a Camera> {
+        match self.entity(id) {
+            Some(Entity::Camera(camera)) => Some(camera),
+            _ => None,
+        }
+    }
+
+    pub fn light(&self, id: i32) -> Option<&'a Light> {
+        match self.entity(id) {
+            Some(Entity::Light(light)) => Some(light),
+            _ => None,
+        }
+    }
+
+    pub fn object(&self, id: i32) -> Option<&'a SceneObject> {
+        match self.entity(id) {
+            Some(Entity::SceneObject(scene_object)) => Some(scene_object),
+            _ => None,
+        }
+    }
+
+    /// Material from `Scene.materials` with given id.
+    pub fn material(&self, id: &str) -> Option<&'a Material> {
+        self.materials.get(id).cloned()
+    }
+
+    /// Entities which have `Transform.parent_id` equal to given id, in declaration order.
+    pub fn children_of(&self, id: i32) -> &[Entity<'a>] {
+        self.children.get(&id).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let mut scene = crate::read("./examples/12.cowscene").unwrap();
+        scene.scene_objects[1].transform.as_mut().unwrap().parent_id = 2;
+        scene.lights[0].transform.as_mut().unwrap().parent_id = 2;
+        scene.materials.push(Material {
+            id: "red".to_string(),
+            material: None,
+        });
+
+        let index = SceneIndex::new(&scene);
+
+        assert_eq!(Some(&scene.cameras[0]), index.camera(1));
+        assert_eq!(None, index.camera(2));
+        assert_eq!(Some(&scene.scene_objects[0]), index.object(2));
+        assert_eq!(Some(&scene.lights[0]), index.light(4));
+        assert_eq!(Some(Entity::Light(&scene.lights[0])), index.entity(4));
+        assert_eq!(None, index.entity(5));
+        assert_eq!(Some(&scene.materials[0]), index.material("red"));
+        assert_eq!(None, index.material("blue"));
+        assert_eq!(&[Entity::SceneObject(&scene.scene_objects[1]), Entity::Light(&scene.lights[0])], index.children_of(2));
+        assert!(index.children_of(1).is_empty());
+    }
+}
@@ -0,0 +1,596 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+/// Numeric field types of the generated messages.
+pub trait ProtoNumber: Sized + Default {
+    fn from_i64(value: i64) -> Option<Self>;
+    fn from_u64(value: u64) -> Option<Self>;
+    fn from_f64(value: f64) -> Option<Self>;
+    fn parse(value: &str) -> Option<Self>;
+}
+
+macro_rules! proto_float {
+    ($($t:ty),*) => {$(
+        impl ProtoNumber for $t {
+            fn from_i64(value: i64) -> Option<Self> {
+                Some(value as $t)
+            }
+
+            fn from_u64(value: u64) -> Option<Self> {
+                Some(value as $t)
+            }
+
+            fn from_f64(value: f64) -> Option<Self> {
+                Some(value as $t)
+            }
+
+            fn parse(value: &str) -> Option<Self> {
+                match value {
+                    "NaN" => Some(<$t>::NAN),
+                    "Infinity" => Some(<$t>::INFINITY),
+                    "-Infinity" => Some(<$t>::NEG_INFINITY),
+                    _ => value.parse().ok(),
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! proto_integer {
+    ($($t:ty),*) => {$(
+        impl ProtoNumber for $t {
+            fn from_i64(value: i64) -> Option<Self> {
+                <$t>::try_from(value).ok()
+            }
+
+            fn from_u64(value: u64) -> Option<Self> {
+                <$t>::try_from(value).ok()
+            }
+
+            // proto3 JSON allows integers in exponent notation, e.g. 1e2
+            fn from_f64(value: f64) -> Option<Self> {
+                if value.fract() == 0.0 && value >= <$t>::MIN as f64 && value <= <$t>::MAX as f64 {
+                    Some(value as $t)
+                } else {
+                    None
+                }
+            }
+
+            fn parse(value: &str) -> Option<Self> {
+                value.parse().ok().or_else(|| value.parse::<f64>().ok().and_then(Self::from_f64))
+            }
+        }
+    )*};
+}
+
+proto_float!(f32, f64);
+proto_integer!(i32, i64, u32, u64);
+
+/// Writes a floating point number as specified by the proto3 JSON mapping: NaN and infinities are strings.
+pub fn float<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer, T: Serialize + Into<f64> + Copy {
+
+    let number: f64 = (*value).into();
+    if number.is_nan() {
+        serializer.serialize_str("NaN")
+    } else if number == f64::INFINITY {
+        serializer.serialize_str("Infinity")
+    } else if number == f64::NEG_INFINITY {
+        serializer.serialize_str("-Infinity")
+    } else {
+        value.serialize(serializer)
+    }
+}
+
+/// Reads a number as specified by the proto3 JSON mapping: either a JSON number or a string,
+/// `null` meaning the default value.
+pub fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where D: Deserializer<'de>, T: ProtoNumber {
+
+    struct NumberVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: ProtoNumber> Visitor<'de> for NumberVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number or a string containing a number")
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+            T::from_i64(value).ok_or_else(|| E::invalid_value(de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+            T::from_u64(value).ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<T, E> {
+            T::from_f64(value).ok_or_else(|| E::invalid_value(de::Unexpected::Float(value), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+            T::parse(value).ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<T, E> {
+            Ok(T::default())
+        }
+    }
+
+    deserializer.deserialize_any(NumberVisitor(PhantomData))
+}
+
+/// Every oneof of scene.proto as (message, oneof JSON name).
+/// Serde writes oneofs of the generated types wrapped into an object under the oneof name,
+/// while in the shorthand form members are written directly into the owning message.
+const ONEOFS: &[(&str, &str)] = &[
+    ("SceneObject", "objectMaterial"),
+    ("SceneObject", "mesh"),
+    ("Light", "light"),
+    ("Camera", "camera"),
+    ("Material", "material"),
+    ("CustomProperty", "value"),
+];
+
+/// Fields through which messages with oneofs are reachable from the Scene: (message, field, field type).
+/// `SceneObject.material` is a oneof member itself, so it is listed in its shorthand form.
+const NESTED_MESSAGES: &[(&str, &str, &str)] = &[
+    ("Scene", "renderOptions", "RenderOptions"),
+    ("Scene", "sceneObjects", "SceneObject"),
+    ("Scene", "lights", "Light"),
+    ("Scene", "cameras", "Camera"),
+    ("Scene", "materials", "Material"),
+    ("RenderOptions", "customProperties", "CustomProperty"),
+    ("SceneObject", "material", "Material"),
+];
+
+/// Messages which can be read from JSON in the shorthand form as well as in the canonical proto3 JSON mapping,
+/// implemented by build.rs for every message of scene.proto.
+pub trait JsonMessage: Default {
+    const NAME: &'static str;
+    /// Names accepted for every field: the proto field name and, if different, its lowerCamelCase JSON name.
+    const FIELDS: &'static [&'static [&'static str]];
+
+    fn read_field<'de, A: MapAccess<'de>>(&mut self, field: usize, map: &mut A) -> Result<(), A::Error>;
+}
+
+pub fn deserialize_message<'de, D, M>(deserializer: D) -> Result<M, D::Error>
+    where D: Deserializer<'de>, M: JsonMessage {
+
+    struct MessageVisitor<M>(PhantomData<M>);
+
+    impl<'de, M: JsonMessage> Visitor<'de> for MessageVisitor<M> {
+        type Value = M;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "{} message", M::NAME)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<M, A::Error> {
+            let mut message = M::default();
+
+            while let Some(field) = map.next_key_seed(FieldSeed(M::FIELDS))? {
+                match field {
+                    Some(field) => message.read_field(field, &mut map)?,
+                    // unknown fields are skipped, same as in the other bindings
+                    None => {
+                        map.next_value::<IgnoredAny>()?;
+                    },
+                }
+            }
+
+            Ok(message)
+        }
+    }
+
+    deserializer.deserialize_map(MessageVisitor(PhantomData))
+}
+
+/// Reads a key of a message as the index of the field in `JsonMessage::FIELDS`.
+struct FieldSeed(&'static [&'static [&'static str]]);
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = Option<usize>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<usize>, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed {
+    type Value = Option<usize>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("field name")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Option<usize>, E> {
+        Ok(self.0.iter().position(|names| names.contains(&value)))
+    }
+}
+
+/// Fails if a member of the oneof has already been read.
+pub fn set_oneof<'de, A: MapAccess<'de>, T>(oneof: &mut Option<T>, value: Option<T>, name: &str) -> Result<(), A::Error> {
+    if value.is_some() {
+        if oneof.is_some() {
+            return Err(de::Error::custom(format!("Only one member of oneof {} can be set", name)));
+        }
+        *oneof = value;
+    }
+    Ok(())
+}
+
+/// Number read as specified by the proto3 JSON mapping, see `number`.
+pub struct Number<T>(pub T);
+
+impl<'de, T: ProtoNumber> Deserialize<'de> for Number<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        number(deserializer).map(Number)
+    }
+}
+
+/// Converts JSON produced by serde for the generated types into the documented shorthand form:
+/// oneof members are written directly into the owning message using proto field names
+/// (`"material"`, `"sphere"`, `"lambert_reflection"`) and unset or empty fields are omitted.
+pub fn to_shorthand(scene: Value) -> Value {
+    let mut scene = remove_empty(scene);
+    flatten_oneofs(&mut scene, "Scene");
+    scene
+}
+
+fn flatten_oneofs(value: &mut Value, message: &str) {
+    let object = match value {
+        Value::Object(object) => object,
+        Value::Array(values) => {
+            for value in values {
+                flatten_oneofs(value, message);
+            }
+            return;
+        },
+        _ => return,
+    };
+
+    for (_, oneof) in ONEOFS.iter().filter(|(owner, _)| *owner == message) {
+        if let Some(Value::Object(members)) = object.remove(*oneof) {
+            for (member, value) in members {
+                object.insert(to_proto_name(&member), value);
+            }
+        }
+    }
+
+    for (_, field, field_type) in NESTED_MESSAGES.iter().filter(|(owner, _, _)| *owner == message) {
+        if let Some(nested) = object.get_mut(*field) {
+            flatten_oneofs(nested, field_type);
+        }
+    }
+
+    // defaults which hand-written files never spell out
+    if object.get("id").and_then(Value::as_str) == Some("") {
+        object.remove("id");
+    }
+    if let Some(transform) = object.get_mut("transform").and_then(Value::as_object_mut) {
+        if transform.get("parentId").and_then(Value::as_i64) == Some(0) {
+            transform.remove("parentId");
+        }
+    }
+}
+
+/// Drops `null` fields and empty arrays, which is how unset optional and repeated fields are written.
+fn remove_empty(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter()
+            .filter(|(_, value)| !value.is_null() && value.as_array().map(|values| !values.is_empty()).unwrap_or(true))
+            .map(|(key, value)| (key, remove_empty(value)))
+            .collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(remove_empty).collect()),
+        other => other,
+    }
+}
+
+/// Inverse of `to_json_name`, used for oneof members written in the shorthand form.
+fn to_proto_name(json_name: &str) -> String {
+    let mut name = String::with_capacity(json_name.len() + 4);
+
+    for c in json_name.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::errors::SceneIOError;
+    use crate::scene_object::{Mesh, ObjectMaterial};
+    use crate::{camera, custom_property, light, material};
+    use prost::Message;
+    use prost_types::FileDescriptorSet;
+
+    /// Same conversion as protoc uses to compute `json_name` of a field.
+    fn to_json_name(name: &str) -> String {
+        let mut json_name = String::with_capacity(name.len());
+        let mut capitalize_next = false;
+
+        for c in name.chars() {
+            if c == '_' {
+                capitalize_next = true;
+            } else if capitalize_next {
+                json_name.push(c.to_ascii_uppercase());
+                capitalize_next = false;
+            } else {
+                json_name.push(c);
+            }
+        }
+
+        json_name
+    }
+
+    #[test]
+    fn numbers_as_strings() {
+        let vector: crate::Vector3 = serde_json::from_str(r#"{"x": "1.5", "y": "-Infinity", "z": 2}"#).unwrap();
+        assert_eq!(1.5, vector.x);
+        assert_eq!(f64::NEG_INFINITY, vector.y);
+        assert_eq!(2.0, vector.z);
+
+        let options: crate::RenderOptions = serde_json::from_str(r#"{"cameraId": "3", "width": 1e3, "height": null}"#).unwrap();
+        assert_eq!(3, options.camera_id);
+        assert_eq!(1000, options.width);
+        assert_eq!(0, options.height);
+
+        assert!(serde_json::from_str::<crate::RenderOptions>(r#"{"width": 1.5}"#).is_err());
+        assert!(serde_json::from_str::<crate::RenderOptions>(r#"{"width": "wide"}"#).is_err());
+    }
+
+    fn decode_object(scene_object: &str) -> crate::SceneObject {
+        let scene = format!(r#"{{"version": 1, "sceneObjects": [{}]}}"#, scene_object);
+        crate::decode(scene.as_bytes()).unwrap().scene_objects.remove(0)
+    }
+
+    fn decode_light(light: &str) -> crate::Light {
+        let scene = format!(r#"{{"version": 1, "lights": [{}]}}"#, light);
+        crate::decode(scene.as_bytes()).unwrap().lights.remove(0)
+    }
+
+    fn decode_camera(camera: &str) -> crate::Camera {
+        let scene = format!(r#"{{"version": 1, "cameras": [{}]}}"#, camera);
+        crate::decode(scene.as_bytes()).unwrap().cameras.remove(0)
+    }
+
+    fn decode_material(material: &str) -> crate::Material {
+        let scene = format!(r#"{{"version": 1, "materials": [{}]}}"#, material);
+        crate::decode(scene.as_bytes()).unwrap().materials.remove(0)
+    }
+
+    fn decode_custom_property(custom_property: &str) -> crate::CustomProperty {
+        let scene = format!(r#"{{"version": 1, "renderOptions": {{"customProperties": [{}]}}}}"#, custom_property);
+        crate::decode(scene.as_bytes()).unwrap().render_options.unwrap().custom_properties.remove(0)
+    }
+
+    #[test]
+    fn scene_object_material_id() {
+        let scene_object = decode_object(r#"{"material_id": "red", "plane": {}}"#);
+        assert_eq!(Some(ObjectMaterial::MaterialId("red".to_string())), scene_object.object_material);
+    }
+
+    #[test]
+    fn scene_object_material() {
+        let scene_object = decode_object(r#"{"material": {"specular_reflection": {"eta": 1.5}}, "plane": {}}"#);
+        assert_eq!(Some(ObjectMaterial::Material(crate::Material {
+            id: "".to_string(),
+            material: Some(material::Material::SpecularReflection(crate::SpecularReflectionMaterial { eta: 1.5 })),
+        })), scene_object.object_material);
+    }
+
+    #[test]
+    fn scene_object_sphere() {
+        assert_eq!(Some(Mesh::Sphere(crate::Sphere { radius: 2.0 })), decode_object(r#"{"sphere": {"radius": 2}}"#).mesh);
+    }
+
+    #[test]
+    fn scene_object_cube() {
+        let mesh = decode_object(r#"{"cube": {"size": {"x": 1, "y": 2, "z": 3}}}"#).mesh;
+        assert_eq!(Some(Mesh::Cube(crate::Cube { size: Some(crate::Vector3 { x: 1.0, y: 2.0, z: 3.0 }) })), mesh);
+    }
+
+    #[test]
+    fn scene_object_plane() {
+        assert_eq!(Some(Mesh::Plane(crate::Plane {})), decode_object(r#"{"plane": {}}"#).mesh);
+    }
+
+    #[test]
+    fn scene_object_disk() {
+        assert_eq!(Some(Mesh::Disk(crate::Disk { radius: 3.0 })), decode_object(r#"{"disk": {"radius": 3}}"#).mesh);
+    }
+
+    #[test]
+    fn scene_object_meshed_object() {
+        let mesh = decode_object(r#"{"meshed_object": {"reference": "cow.obj"}}"#).mesh;
+        assert_eq!(Some(Mesh::MeshedObject(crate::MeshedObject { reference: "cow.obj".to_string(), obj: None })), mesh);
+    }
+
+    #[test]
+    fn light_point() {
+        assert_eq!(Some(light::Light::Point(crate::LightPoint {})), decode_light(r#"{"point": {}}"#).light);
+    }
+
+    #[test]
+    fn light_directional() {
+        assert_eq!(Some(light::Light::Directional(crate::LightDirectional {})), decode_light(r#"{"directional": {}}"#).light);
+    }
+
+    #[test]
+    fn light_sphere() {
+        assert_eq!(Some(light::Light::Sphere(crate::LightSphere { radius: 0.5 })), decode_light(r#"{"sphere": {"radius": 0.5}}"#).light);
+    }
+
+    #[test]
+    fn light_environment() {
+        assert_eq!(Some(light::Light::Environment(crate::LightEnvironment {})), decode_light(r#"{"environment": {}}"#).light);
+    }
+
+    #[test]
+    fn camera_perspective() {
+        let camera = decode_camera(r#"{"perspective": {"fov": 90}}"#).camera;
+        assert_eq!(Some(camera::Camera::Perspective(crate::PerspectiveCamera { fov: 90.0 })), camera);
+    }
+
+    #[test]
+    fn camera_orthographic() {
+        let camera = decode_camera(r#"{"orthographic": {}}"#).camera;
+        assert_eq!(Some(camera::Camera::Orthographic(crate::OrthographicCamera {})), camera);
+    }
+
+    #[test]
+    fn material_lambert_reflection() {
+        let material = decode_material(r#"{"id": "red", "lambert_reflection": {"color": {"r": 1, "g": 0, "b": 0}}}"#);
+        assert_eq!(Some(material::Material::LambertReflection(crate::LambertReflectionMaterial {
+            color: Some(crate::Color { r: 1.0, g: 0.0, b: 0.0 }),
+        })), material.material);
+    }
+
+    #[test]
+    fn material_specular_reflection() {
+        let material = decode_material(r#"{"id": "glass", "specular_reflection": {"eta": 1.5}}"#);
+        assert_eq!(Some(material::Material::SpecularReflection(crate::SpecularReflectionMaterial { eta: 1.5 })), material.material);
+    }
+
+    #[test]
+    fn custom_property_text_value() {
+        let custom_property = decode_custom_property(r#"{"key": "name", "text_value": "cow"}"#);
+        assert_eq!(Some(custom_property::Value::TextValue("cow".to_string())), custom_property.value);
+    }
+
+    #[test]
+    fn custom_property_int32_value() {
+        let custom_property = decode_custom_property(r#"{"key": "samples", "int32_value": 16}"#);
+        assert_eq!(Some(custom_property::Value::Int32Value(16)), custom_property.value);
+    }
+
+    #[test]
+    fn several_oneof_members_are_rejected() {
+        let scene = r#"{"version": 1, "sceneObjects": [{"sphere": {"radius": 1}, "disk": {"radius": 1}}]}"#;
+        assert!(matches!(crate::decode_as(scene.as_bytes(), crate::Format::Json), Err(SceneIOError::FailedToDecode { .. })));
+    }
+
+    #[test]
+    fn serde_form_is_accepted() {
+        let scene = crate::read("./examples/11.cowscene").unwrap();
+        let wrapped = serde_json::to_vec(&scene).unwrap();
+
+        assert_eq!(scene, crate::decode(&wrapped).unwrap());
+    }
+
+    #[test]
+    fn oneof_table_covers_scene_proto() {
+        let descriptor_set = FileDescriptorSet::decode(&include_bytes!(concat!(env!("OUT_DIR"), "/scene_format_descriptor_set.bin"))[..]).unwrap();
+
+        for message in descriptor_set.file.iter().flat_map(|file| file.message_type.iter()) {
+            for oneof in &message.oneof_decl {
+                assert!(ONEOFS.contains(&(message.name(), to_json_name(oneof.name()).as_str())),
+                    "{}.{} is missing from ONEOFS", message.name(), oneof.name());
+            }
+        }
+    }
+
+    #[test]
+    fn nested_messages_table_covers_scene_proto() {
+        let descriptor_set = FileDescriptorSet::decode(&include_bytes!(concat!(env!("OUT_DIR"), "/scene_format_descriptor_set.bin"))[..]).unwrap();
+        let messages: Vec<_> = descriptor_set.file.iter().flat_map(|file| file.message_type.iter()).collect();
+        let message_fields = |message: &prost_types::DescriptorProto| message.field.iter()
+            .filter(|field| field.r#type() == prost_types::field_descriptor_proto::Type::Message)
+            .map(|field| (to_json_name(field.name()), field.type_name().rsplit('.').next().unwrap().to_string()))
+            .collect::<Vec<_>>();
+
+        // messages with oneofs and the ones from which they are reachable
+        let mut with_oneofs: Vec<&str> = messages.iter().filter(|message| !message.oneof_decl.is_empty()).map(|message| message.name()).collect();
+        loop {
+            let reaching: Vec<&str> = messages.iter()
+                .filter(|message| !with_oneofs.contains(&message.name()))
+                .filter(|message| message_fields(message).iter().any(|(_, field_type)| with_oneofs.contains(&field_type.as_str())))
+                .map(|message| message.name())
+                .collect();
+            if reaching.is_empty() {
+                break;
+            }
+            with_oneofs.extend(reaching);
+        }
+
+        let mut expected = Vec::new();
+        for message in &messages {
+            for (field, field_type) in message_fields(message) {
+                if with_oneofs.contains(&field_type.as_str()) {
+                    expected.push((message.name().to_string(), field, field_type));
+                }
+            }
+        }
+        let mut actual: Vec<_> = NESTED_MESSAGES.iter()
+            .map(|(message, field, field_type)| (message.to_string(), field.to_string(), field_type.to_string()))
+            .collect();
+        expected.sort();
+        actual.sort();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn shorthand_output() {
+        let scene = crate::decode(&std::fs::read("./examples/11.cowscene").unwrap()).unwrap();
+
+        let shorthand = to_shorthand(serde_json::to_value(&scene).unwrap());
+
+        assert_eq!(serde_json::json!({
+            "id": 2,
+            "transform": {
+                "position": {"x": 0.0, "y": 1.0, "z": 0.0},
+                "rotation": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "scale": {"x": 1.0, "y": 1.0, "z": 1.0},
+            },
+            "material": {
+                "lambert_reflection": {
+                    "color": {"r": 0.0, "g": 0.0, "b": 1.0},
+                },
+            },
+            "sphere": {"radius": 1.0},
+        }), shorthand["sceneObjects"][0]);
+        assert_eq!(serde_json::json!({
+            "id": 5,
+            "transform": {
+                "position": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "rotation": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "scale": {"x": 1.0, "y": 1.0, "z": 1.0},
+            },
+            "color": {"r": 3.0, "g": 3.0, "b": 3.0},
+            "environment": {},
+        }), shorthand["lights"][0]);
+        assert_eq!(serde_json::json!({"fov": 60.0}), shorthand["cameras"][0]["perspective"]);
+        assert_eq!(None, shorthand.get("materials"));
+    }
+
+    #[test]
+    fn shorthand_of_referenced_material_and_custom_property() {
+        let mut scene = crate::decode(&std::fs::read("./examples/11.cowscene").unwrap()).unwrap();
+        crate::materials::canonicalize_materials(&mut scene, crate::materials::MaterialForm::Referenced).unwrap();
+        scene.render_options.as_mut().unwrap().custom_properties.push(crate::CustomProperty {
+            key: "samples".to_string(),
+            value: Some(crate::custom_property::Value::Int32Value(16)),
+        });
+
+        let shorthand = to_shorthand(serde_json::to_value(&scene).unwrap());
+
+        assert_eq!(serde_json::json!("material_1"), shorthand["sceneObjects"][0]["material_id"]);
+        assert_eq!(serde_json::json!({"id": "material_2", "specular_reflection": {"eta": 2.0}}), shorthand["materials"][1]);
+        assert_eq!(serde_json::json!([{"key": "samples", "int32_value": 16}]), shorthand["renderOptions"]["customProperties"]);
+    }
+}
@@ -1,19 +1,70 @@
 #[macro_use] extern crate log;
 extern crate custom_error;
 
+pub mod assets;
+mod atomic;
+pub mod compression;
+pub mod defaults;
+pub mod envelope;
 pub mod errors;
+pub mod hierarchy;
+pub mod index;
+mod json;
+pub mod materials;
+pub mod mtl;
 pub mod obj;
+pub mod transform;
+pub mod validation;
 
-use std::{env, fs::File, path::Path};
-use std::io::Write;
+use std::{borrow::Cow, env, fs::File, sync::Arc};
+use std::path::{Path, PathBuf};
+use std::io::{Read, Write};
 
 use prost::Message;
-use serde_json::{Map, Value};
 
+use assets::{AssetResolver, FileSystemResolver};
+use compression::Compression;
+use defaults::apply_defaults;
 use errors::SceneIOError;
-use obj::read_obj_file;
+
+pub use validation::validate;
 
 include!(concat!(env!("OUT_DIR"), "/scene_format.rs"));
+include!(concat!(env!("OUT_DIR"), "/scene_format_annotations.rs"));
+include!(concat!(env!("OUT_DIR"), "/scene_format_json.rs"));
+
+/// Encoding of a scene file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Binary,
+}
+
+impl Format {
+
+    fn other(self) -> Format {
+        match self {
+            Format::Json => Format::Binary,
+            Format::Binary => Format::Json,
+        }
+    }
+}
+
+/// Guesses the format of encoded scene: JSON scenes are objects, so they start with `{`
+/// after optional whitespace and byte order mark. A binary scene can not start with `{`,
+/// because there is no field with number 15 in Scene.
+pub fn detect_format(data: &[u8]) -> Format {
+    if envelope::has_header(data) {
+        return Format::Binary;
+    }
+
+    let data = data.strip_prefix(b"\xEF\xBB\xBF".as_ref()).unwrap_or(data);
+
+    match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') => Format::Json,
+        _ => Format::Binary,
+    }
+}
 
 pub fn encode(scene: &Scene) -> Result<Vec<u8>, SceneIOError> {
     let mut buf = Vec::with_capacity(scene.encoded_len());
@@ -23,442 +74,370 @@ pub fn encode(scene: &Scene) -> Result<Vec<u8>, SceneIOError> {
     Ok(buf)
 }
 
-pub fn encode_json(scene: &Scene) -> Result<Vec<u8>, SceneIOError> {
-    serde_json::to_vec_pretty(&scene).map_err(|err| SceneIOError::FailedToEncode {
+/// Same as `encode`, but with `envelope::Header` in front, which is what `save_with` writes with `SaveOptions::envelope`.
+/// Readers of the other languages expect plain protobuf as produced by `encode`.
+pub fn encode_with_envelope(scene: &Scene) -> Result<Vec<u8>, SceneIOError> {
+    let mut buf = Vec::with_capacity(envelope::HEADER_LEN + scene.encoded_len());
+    envelope::write_header(&mut buf, envelope::Header {
+        version: envelope::VERSION,
+        flags: 0,
+    });
+    scene.encode(&mut buf).map_err(|err| SceneIOError::FailedToEncode {
         description: err.to_string(),
-    })
+    })?;
+    Ok(buf)
 }
 
-pub fn save(scene: &Scene, save_to: &str) -> Result<(), SceneIOError> {
-    let encoded = encode(scene)?;
-    let mut file = File::create(save_to)?;
-    file.write(&encoded)?;
-    Ok(())
+/// Encodes the scene in the shorthand JSON form used by the docs and examples.
+pub fn encode_json(scene: &Scene) -> Result<Vec<u8>, SceneIOError> {
+    let mut buf = Vec::new();
+    write_json_to(scene, &mut buf)?;
+    Ok(buf)
 }
 
-pub fn save_json(scene: &Scene, save_to: &str) -> Result<(), SceneIOError> {
-    let encoded = encode_json(scene)?;
-    let mut file = File::create(save_to)?;
-    file.write(&encoded)?;
+/// Writes the scene in the same form as `save`, without compression.
+pub fn write_to<W: Write>(scene: &Scene, mut writer: W) -> Result<(), SceneIOError> {
+    writer.write_all(&encode(scene)?)?;
     Ok(())
 }
 
-pub fn decode(data: &[u8]) -> Result<Scene, SceneIOError> {
-    decode_with_context(data, None)
-}
-
-pub fn decode_with_context(data: &[u8], context: Option<&Path>) -> Result<Scene, SceneIOError> {
-    let value: Value = match serde_json::from_slice(data) {
-        Ok(v) => v,
-        Err(err) => {
-            debug!("Failed to decode as json, trying binary: {:?}", err);
-            return Scene::decode(&*data.to_vec())
-                .map_err(|_| SceneIOError::FailedToDecode { description: err.to_string() })
-                .and_then(|scene| post_process_scene(&scene, context))
-        }
-    };
-
-    let mut scene: Map<String, Value> = match value {
-        Value::Object(obj) => obj,
-        _ => return Err(SceneIOError::FailedToDecode {
-            description: "Expected top level structure to be object".to_string(),
-        }),
-    };
-
-    if let Some(render_options) = scene.get("renderOptions") {
-        let render_options = match &render_options {
-            Value::Object(obj) => obj,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected render options to be an object".to_string(),
-            })
-        };
-
-        scene.insert("renderOptions".to_string(), Value::Object(pre_process_render_options(render_options)?));
-    }
-
-    if let Some(cameras) = scene.get("cameras") {
-        let cameras = match &cameras {
-            Value::Array(arr) => arr,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected cameras to be an array".to_string(),
-            })
-        };
-
-        scene.insert("cameras".to_string(), Value::Array(pre_process_cameras(&cameras)?));
-    }
-
-    if let Some(scene_objects) = scene.get("sceneObjects") {
-        let scene_objects = match scene_objects {
-            Value::Array(arr) => arr,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected sceneObjects to be an array".to_string(),
-            })
-        };
-
-        scene.insert("sceneObjects".to_string(), Value::Array(pre_process_scene_objects(&scene_objects)?));
-    }
-
-    if let Some(lights) = scene.get("lights") {
-        let lights = match &lights {
-            Value::Array(arr) => arr,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected lights to be an array".to_string(),
-            })
-        };
-
-        scene.insert("lights".to_string(), Value::Array(pre_process_lights(&lights)?));
-    } else {
-        scene.insert("lights".to_string(), Value::Array(Vec::new()));
-    }
-
-    if !scene.contains_key("materials") {
-        scene.insert("materials".to_string(), Value::Array(Vec::new()));
-    }
-
-    let scene: Scene = serde_json::from_str(&match serde_json::to_string(&scene) {
-        Ok(v) => v,
-        Err(err) => return Err(SceneIOError::FailedToEncode {
-            description: err.to_string(),
-        }),
-    }).expect("expected json to be valid");
+/// Writes the scene in the same form as `save_json`, without compression.
+pub fn write_json_to<W: Write>(scene: &Scene, writer: W) -> Result<(), SceneIOError> {
+    let value = serde_json::to_value(scene).map_err(|err| SceneIOError::FailedToEncode {
+        description: err.to_string(),
+    })?;
 
-    post_process_scene(&scene, context)
+    serde_json::to_writer_pretty(writer, &json::to_shorthand(value)).map_err(|err| SceneIOError::FailedToEncode {
+        description: err.to_string(),
+    })
 }
 
-fn pre_process_render_options(render_options: &Map<String, Value>) -> Result<Map<String, Value>, SceneIOError> {
-    let mut render_options = render_options.clone();
-
-    if !render_options.contains_key("customProperties") {
-        render_options.insert("customProperties".to_string(), Value::Array(Vec::new()));
-    }
-
-    Ok(render_options)
+/// How `save_with` and `save_json_with` write files.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Flush the file and its directory to disk before returning.
+    pub sync: bool,
+    /// Directory the relative references of meshed objects are relative to, for scenes which were not read from
+    /// a file with `read`. If it is set, they are rewritten to be relative to the directory of the saved file.
+    pub assets_directory: Option<PathBuf>,
+    /// Write `envelope::Header` in front of binary scenes. Off by default, as only this library can read it for now.
+    pub envelope: bool,
 }
 
-fn pre_process_cameras(cameras: &Vec<Value>) -> Result<Vec<Value>, SceneIOError> {
-    let mut new_cameras = Vec::new();
-
-    for camera in cameras {
-        new_cameras.push(Value::Object(match &camera {
-            Value::Object(camera) => pre_process_camera(camera)?,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected camera to be an object".to_string(),
-            })
-        }));
-    }
-
-    Ok(new_cameras)
+/// Saves binary scene, compressed if the file name ends with `.gz` or `.zst`.
+///
+/// `read` resolves references of meshed objects against the directory of the scene file, they are written
+/// relative to the directory of the saved file. So a scene read from one directory and saved to another one
+/// still points to the same obj files.
+pub fn save<P: AsRef<Path>>(scene: &Scene, save_to: P) -> Result<(), SceneIOError> {
+    save_with(scene, save_to, &SaveOptions::default())
 }
 
-fn pre_process_camera(camera: &Map<String, Value>) -> Result<Map<String, Value>, SceneIOError> {
-    let mut camera = camera.clone();
-
-    if let Some(transform) = camera.get("transform") {
-        camera["transform"] = Value::Object(match &transform {
-            Value::Object(transform) => pre_process_transform(transform)?,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected transform to be an object".to_string(),
-            })
-        })
-    }
-
-    Ok(camera)
+/// Same as `save`. The file is replaced atomically: it either keeps the old content or has the new one.
+pub fn save_with<P: AsRef<Path>>(scene: &Scene, save_to: P, options: &SaveOptions) -> Result<(), SceneIOError> {
+    let save_to = save_to.as_ref();
+    let scene = with_rebased_references(scene, save_to, options);
+    let encoded = if options.envelope {
+        encode_with_envelope(&scene)?
+    } else {
+        encode(&scene)?
+    };
+    let encoded = compression::compress(encoded, Compression::from_path(save_to))?;
+    atomic::write_atomically(save_to, &encoded, options.sync)
 }
 
-
-fn pre_process_scene_objects(scene_objects: &Vec<Value>) -> Result<Vec<Value>, SceneIOError> {
-    let mut objects = Vec::new();
-
-    for object in scene_objects {
-        objects.push(Value::Object(match object {
-            Value::Object(scene_object) => pre_process_scene_object(scene_object)?,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected scene object to be an object".to_string(),
-            })
-        }))
-    }
-
-    Ok(objects)
+/// Saves JSON scene, compressed if the file name ends with `.gz` or `.zst`.
+/// References of meshed objects are rewritten the same as with `save`.
+pub fn save_json<P: AsRef<Path>>(scene: &Scene, save_to: P) -> Result<(), SceneIOError> {
+    save_json_with(scene, save_to, &SaveOptions::default())
 }
 
-fn pre_process_scene_object(scene_object: &Map<String, Value>) -> Result<Map<String, Value>, SceneIOError> {
-    let mut scene_object = scene_object.clone();
-
-    if let Some(transform) = scene_object.get("transform") {
-        scene_object["transform"] = Value::Object(match &transform {
-            Value::Object(transform) => pre_process_transform(transform)?,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected transform to be an object".to_string(),
-            })   
-        })
-    }
-
-    // object_material
-    if let Some(material) = scene_object.get("material") {
-        let material = match material {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected material to be an object".to_string(),
-            })
-        };
-
-        let material = pre_process_material(&material)?;
+/// Same as `save_json`. The file is replaced atomically: it either keeps the old content or has the new one.
+pub fn save_json_with<P: AsRef<Path>>(scene: &Scene, save_to: P, options: &SaveOptions) -> Result<(), SceneIOError> {
+    let save_to = save_to.as_ref();
+    let scene = with_rebased_references(scene, save_to, options);
+    let encoded = compression::compress(encode_json(&scene)?, Compression::from_path(save_to))?;
+    atomic::write_atomically(save_to, &encoded, options.sync)
+}
 
-        scene_object.insert("objectMaterial".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("material".to_string(), Value::Object(material));
-            map
-        }));
+fn with_rebased_references<'a>(scene: &'a Scene, save_to: &Path, options: &SaveOptions) -> Cow<'a, Scene> {
+    if options.assets_directory.is_none() && !assets::has_absolute_references(scene) {
+        return Cow::Borrowed(scene);
     }
 
-    // mesh
-    if let Some(sphere) = scene_object.get("sphere") {
-        let sphere = match sphere {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected sphere to be an object".to_string(),
-            })
-        };
-
-        scene_object.insert("mesh".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("sphere".to_string(), Value::Object(sphere));
-            map
-        }));
+    let directory = save_to.parent().unwrap_or_else(|| Path::new(""));
+    let mut scene = scene.clone();
+    if let Some(assets_directory) = &options.assets_directory {
+        assets::rebase_references(&mut scene, assets_directory, directory);
     }
+    assets::relativize_references(&mut scene, directory);
+    Cow::Owned(scene)
+}
 
-    if let Some(cube) = scene_object.get("cube") {
-        let cube = match cube {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected cube to be an object".to_string(),
-            })
-        };
+/// When obj files referenced by `MeshedObject.reference` are loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshLoading {
+    /// While the scene is read.
+    Eager,
+    /// Only when requested with `load_meshes`.
+    Lazy,
+    /// Never, `MeshedObject.obj` is only set if it is embedded into the scene.
+    Never,
+}
 
-        scene_object.insert("mesh".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("cube".to_string(), Value::Object(cube));
-            map
-        }));
-    }
+/// What happens to scenes that do not pass `validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Validation {
+    /// The scene is rejected with `SceneIOError::InvalidScene`.
+    Strict,
+    /// Validation errors are logged as warnings.
+    Warn,
+    /// The scene is not validated.
+    Skip,
+}
 
-    if let Some(plane) = scene_object.get("plane") {
-        let plane = match plane {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected plane to be an object".to_string(),
-            })
-        };
+/// How `read_with` and `decode_with` read scenes. The default is what `read` does.
+#[derive(Clone)]
+pub struct ReadOptions {
+    /// Source of the obj files. `read_with` uses the directory of the scene file if it is not set,
+    /// `decode_with` does not load meshes without it.
+    pub asset_resolver: Option<Arc<dyn AssetResolver>>,
+    pub mesh_loading: MeshLoading,
+    pub validation: Validation,
+    /// Fill in the default values from the format spec.
+    pub apply_defaults: bool,
+    /// Limit in bytes for the scene file, both as stored and decompressed.
+    pub max_scene_size: Option<u64>,
+    /// Limit in bytes for each of the assets, both obj and mtl files.
+    pub max_asset_size: Option<u64>,
+    /// Add materials of the mtl files referenced by obj files to `Scene.materials`, see `obj::read_obj_mesh`.
+    /// Materials of the faces are returned by `load_meshes`, `read_with_face_materials` and `decode_with_face_materials`,
+    /// `read_with` and `decode_with` drop them.
+    pub import_materials: bool,
+    /// Directory of the scene, relative references of meshed objects are resolved against it to absolute paths,
+    /// see `assets::resolve_references`. `read_with` sets it to the directory of the scene file
+    /// if `asset_resolver` is not set.
+    pub scene_directory: Option<PathBuf>,
+}
 
-        scene_object.insert("mesh".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("plane".to_string(), Value::Object(plane));
-            map
-        }));
+impl Default for ReadOptions {
+
+    fn default() -> ReadOptions {
+        ReadOptions {
+            asset_resolver: None,
+            mesh_loading: MeshLoading::Eager,
+            validation: Validation::Strict,
+            apply_defaults: true,
+            max_scene_size: None,
+            max_asset_size: None,
+            import_materials: false,
+            scene_directory: None,
+        }
     }
+}
 
-    if let Some(disk) = scene_object.get("disk") {
-        let disk = match disk {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected disk to be an object".to_string(),
-            })
-        };
+impl ReadOptions {
 
-        scene_object.insert("mesh".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("disk".to_string(), Value::Object(disk));
-            map
-        }));
+    /// Options of `decode` and `decode_as`, which do not validate scenes.
+    fn decode() -> ReadOptions {
+        ReadOptions {
+            validation: Validation::Skip,
+            ..ReadOptions::default()
+        }
     }
 
-    if let Some(meshed_object) = scene_object.get("meshed_object") {
-        let meshed_object = match meshed_object {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected meshed object to be an object".to_string(),
-            })
-        };
+    fn with_context(&self, context: Option<&Path>) -> ReadOptions {
+        if self.asset_resolver.is_some() {
+            return self.clone();
+        }
 
-        scene_object.insert("mesh".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("meshedObject".to_string(), Value::Object(meshed_object));
-            map
-        }));
+        ReadOptions {
+            asset_resolver: context.map(|context| Arc::new(FileSystemResolver::new(context)) as Arc<dyn AssetResolver>),
+            scene_directory: self.scene_directory.clone().or_else(|| context.map(Path::to_path_buf)),
+            ..self.clone()
+        }
     }
-
-    Ok(scene_object)
 }
 
-fn pre_process_material(material: &Map<String, Value>) -> Result<Map<String, Value>, SceneIOError> {
-    let mut material = material.clone();
-
-    if !material.contains_key("id") {
-        material.insert("id".to_string(), Value::String("".to_string()));
-    }
-
-    if let Some(lambert_reflection) = material.get("lambert_reflection") {
-        let lambert_reflection = match lambert_reflection {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected lambert reflection to be an object".to_string(),
-            })
-        };
+/// Decodes the scene, detecting its compression and format with `detect_format`.
+/// If the scene can not be decoded in the detected format, the other one is tried as well.
+pub fn decode(data: &[u8]) -> Result<Scene, SceneIOError> {
+    decode_with(data, &ReadOptions::decode())
+}
 
-        material.insert("material".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("lambertReflection".to_string(), Value::Object(lambert_reflection));
-            map
-        }));
-    }
+/// Same as `decode`, loading meshes relative to the context directory if it is given.
+/// Prefer `decode_with`, which allows to choose the source of meshes explicitly.
+pub fn decode_with_context(data: &[u8], context: Option<&Path>) -> Result<Scene, SceneIOError> {
+    decode_with(data, &ReadOptions::decode().with_context(context))
+}
 
-    if let Some(specular_reflection) = material.get("specular_reflection") {
-        let specular_reflection = match specular_reflection {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected specular reflection to be an object".to_string(),
-            })
-        };
+/// Same as `decode`, loading meshes, applying defaults and validating as set in the options.
+/// Materials of the faces imported with `options.import_materials` are dropped, see `decode_with_face_materials`.
+pub fn decode_with(data: &[u8], options: &ReadOptions) -> Result<Scene, SceneIOError> {
+    Ok(decode_with_face_materials(data, options)?.0)
+}
 
-        material.insert("material".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("specularReflection".to_string(), Value::Object(specular_reflection));
-            map
-        }));
-    }
+/// Same as `decode_with`, also returning the materials of the faces of meshes loaded with `MeshLoading::Eager`,
+/// as `load_meshes` does. It is empty unless `options.import_materials` is set.
+pub fn decode_with_face_materials(data: &[u8], options: &ReadOptions) -> Result<(Scene, materials::FaceMaterials), SceneIOError> {
+    check_size("Scene", data.len(), options.max_scene_size)?;
+    let data = compression::decompress_limited(data, options.max_scene_size)?;
+    let data = data.as_ref();
+    let format = detect_format(data);
+
+    let scene = match decode_structure(data, format) {
+        Ok(scene) => scene,
+        Err(detected_format_error) => {
+            debug!("Failed to decode as {:?}, trying {:?}: {}", format, format.other(), detected_format_error);
+
+            match decode_structure(data, format.other()) {
+                Ok(scene) => scene,
+                Err(other_format_error) => {
+                    let (json_error, binary_error) = match format {
+                        Format::Json => (detected_format_error, other_format_error),
+                        Format::Binary => (other_format_error, detected_format_error),
+                    };
+                    return Err(SceneIOError::UnknownFormat { json_error, binary_error });
+                },
+            }
+        },
+    };
 
-    Ok(material)
+    post_process_scene(scene, options)
 }
 
-fn pre_process_lights(lights: &Vec<Value>) -> Result<Vec<Value>, SceneIOError> {
-    let mut new_lights = Vec::new();
+/// Decodes the scene in the given format only, compression is still detected.
+pub fn decode_as(data: &[u8], format: Format) -> Result<Scene, SceneIOError> {
+    let scene = decode_structure(&compression::decompress(data)?, format).map_err(|description| SceneIOError::FailedToDecode { description })?;
+    Ok(post_process_scene(scene, &ReadOptions::decode())?.0)
+}
 
-    for light in lights {
-        new_lights.push(Value::Object(match light {
-            Value::Object(light) => pre_process_light(light)?,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected light to be an object".to_string(),
-            })
-        }))
+fn decode_structure(data: &[u8], format: Format) -> Result<Scene, String> {
+    match format {
+        Format::Json => serde_json::from_slice(data).map_err(|err| err.to_string()),
+        Format::Binary => {
+            let (_, payload) = envelope::split(data)?;
+            Scene::decode(payload).map_err(|err| err.to_string())
+        },
     }
-
-    Ok(new_lights)
 }
 
-fn pre_process_light(light: &Map<String, Value>) -> Result<Map<String, Value>, SceneIOError> {
-    let mut light = light.clone();
-
-    if let Some(transform) = light.get("transform") {
-        light["transform"] = Value::Object(match &transform {
-            Value::Object(transform) => pre_process_transform(transform)?,
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected transform to be an object".to_string(),
-            })
-        })
+fn post_process_scene(mut scene: Scene, options: &ReadOptions) -> Result<(Scene, materials::FaceMaterials), SceneIOError> {
+    for scene_object in &scene.scene_objects {
+        if scene_object.mesh.is_none() {
+            return Err(SceneIOError::FailedToDecode {
+                description: "Expected scene object to contain mesh".to_string(),
+            });
+        }
     }
 
-    if let Some(point) = light.get("point") {
-        let point_light = match point {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected point light to be an object".to_string(),
-            })
-        };
+    let face_materials = if options.mesh_loading == MeshLoading::Eager && options.asset_resolver.is_some() {
+        load_meshes(&mut scene, options)?
+    } else {
+        materials::FaceMaterials::new()
+    };
 
-        light.insert("light".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("point".to_string(), Value::Object(point_light));
-            map
-        }));
+    if let Some(scene_directory) = &options.scene_directory {
+        assets::resolve_references(&mut scene, scene_directory);
     }
 
-    if let Some(directional) = light.get("directional") {
-        let directional_light = match directional {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected directional light to be an object".to_string(),
-            })
-        };
-
-        light.insert("light".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("directional".to_string(), Value::Object(directional_light));
-            map
-        }));
+    if options.apply_defaults {
+        apply_defaults(&mut scene);
     }
 
-    if let Some(environment) = light.get("environment") {
-        let environment_light = match environment {
-            Value::Object(v) => v.clone(),
-            _ => return Err(SceneIOError::FailedToDecode {
-                description: "Expected environment light to be an object".to_string(),
-            })
-        };
+    if options.validation != Validation::Skip {
+        if let Err(errors) = validate(&scene) {
+            if options.validation == Validation::Strict {
+                return Err(SceneIOError::InvalidScene { errors });
+            }
 
-        light.insert("environment".to_string(), Value::Object({
-            let mut map = Map::new();
-            map.insert("environment".to_string(), Value::Object(environment_light));
-            map
-        }));
+            for error in errors {
+                warn!("Invalid scene: {}", error);
+            }
+        }
     }
 
-    Ok(light)
+    Ok((scene, face_materials))
 }
 
-fn pre_process_transform(transform: &Map<String, Value>) -> Result<Map<String, Value>, SceneIOError> {
-    let mut transform = transform.clone();
+/// Loads obj files of meshed objects with `options.asset_resolver`, e.g. when the scene is read with `MeshLoading::Lazy`.
+/// The resolver has to be set: the directory of the scene used by `read_with` is not known here,
+/// pass `FileSystemResolver::new(scene_directory)` to load meshes relative to it.
+/// With `options.import_materials`, returns the materials of the faces for meshes which set them with `usemtl`.
+pub fn load_meshes(scene: &mut Scene, options: &ReadOptions) -> Result<materials::FaceMaterials, SceneIOError> {
+    let asset_resolver = options.asset_resolver.as_ref().ok_or(SceneIOError::NoAssetResolver)?;
+    let mut face_materials = materials::FaceMaterials::new();
+
+    for scene_object in &mut scene.scene_objects {
+        if let Some(scene_object::Mesh::MeshedObject(meshed_object)) = &mut scene_object.mesh {
+            if meshed_object.reference.is_empty() {
+                continue;
+            }
 
-    if !transform.contains_key("parentId") {
-        transform.insert("parentId".to_string(), Value::Number(0.into()));
+            let mesh = load_mesh(&meshed_object.reference, asset_resolver.as_ref(), options.max_asset_size)?;
+            if options.import_materials && mesh.face_materials.iter().any(Option::is_some) {
+                let ids = materials::import_obj_materials(&mut scene.materials, &mut scene_object.object_material, &mesh);
+                face_materials.insert(scene_object.id, ids);
+            }
+            meshed_object.obj = Some(mesh.geometry);
+        }
     }
 
-    Ok(transform)
+    Ok(face_materials)
 }
 
-fn post_process_scene(scene: &Scene, context: Option<&Path>) -> Result<Scene, SceneIOError> {
-    let mut scene = scene.clone();
+fn load_mesh(reference: &str, asset_resolver: &dyn AssetResolver, max_asset_size: Option<u64>) -> Result<obj::ObjMesh, SceneIOError> {
+    let data = asset_resolver.read_limited(reference, max_asset_size)?;
+    obj::read_obj_mesh(data.as_slice(), reference, asset_resolver, max_asset_size)
+}
 
-    for i in 0..scene.scene_objects.len() {
-        scene.scene_objects[i] = post_process_scene_object(&scene.scene_objects[i], context)?;
+pub(crate) fn check_size(name: &str, size: usize, limit: Option<u64>) -> Result<(), SceneIOError> {
+    match limit {
+        Some(limit) if size as u64 > limit => Err(SceneIOError::TooLarge {
+            name: name.to_string(),
+            limit,
+        }),
+        _ => Ok(()),
     }
-
-    Ok(scene)
 }
 
-fn post_process_scene_object(scene_object: &SceneObject, context: Option<&Path>) -> Result<SceneObject, SceneIOError> {
-    let mut scene_object = scene_object.clone();
-    let mesh = match &scene_object.mesh {
-        Some(v) => v.clone(),
-        None => return Err(SceneIOError::FailedToDecode {
-            description: "Expected scene object to contain mesh".to_string(),
-        })
-    };
-
-    if let scene_object::Mesh::MeshedObject(meshed_object) = &mesh {
-        let mut meshed_object = meshed_object.clone();
-
-        if let Some(context) = context {
-            if meshed_object.reference != "" {
-                meshed_object.reference = context.join(meshed_object.reference).to_str().ok_or(SceneIOError::FailedToDecode {
-                    description: "Failed to join reference path with context".to_string(),
-                })?.to_string();
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Scene, SceneIOError> {
+    read_with(path, &ReadOptions::default())
+}
 
-                meshed_object.obj = Some(read_obj_file(&meshed_object.reference)?);
-            }
-        }
+/// Reads a scene in any format. Meshes are loaded relative to the directory of the scene,
+/// unless `options.asset_resolver` is set. Relative references of meshed objects are resolved against
+/// the directory then, so that `save` can write them relative to another one.
+pub fn read_with<P: AsRef<Path>>(path: P, options: &ReadOptions) -> Result<Scene, SceneIOError> {
+    Ok(read_with_face_materials(path, options)?.0)
+}
 
-        scene_object.mesh = Some(scene_object::Mesh::MeshedObject(meshed_object));
-    }
+/// Same as `read_with`, also returning the materials of the faces, see `decode_with_face_materials`.
+pub fn read_with_face_materials<P: AsRef<Path>>(path: P, options: &ReadOptions) -> Result<(Scene, materials::FaceMaterials), SceneIOError> {
+    let path = path.as_ref();
+    let options = options.with_context(path.parent());
+    decode_with_face_materials(&read_limited(File::open(path)?, options.max_scene_size)?, &options)
+}
 
-    Ok(scene_object)
+/// Reads and validates a scene in any format, the same way as `read`.
+/// Meshes are loaded relative to the context directory, if it is given.
+pub fn read_from<R: Read>(reader: R, context: Option<&Path>) -> Result<Scene, SceneIOError> {
+    read_from_with(reader, &ReadOptions::default().with_context(context))
 }
 
-pub fn read(read_from: &str) -> Result<Scene, SceneIOError> {
-    let file_path = Path::new(read_from);
-    let parent_directory_path = file_path.parent();
+/// Same as `decode_with`, for the content of the reader.
+pub fn read_from_with<R: Read>(reader: R, options: &ReadOptions) -> Result<Scene, SceneIOError> {
+    decode_with(&read_limited(reader, options.max_scene_size)?, options)
+}
 
-    let data = std::fs::read(file_path)?;
-    decode_with_context(&data, parent_directory_path)
+/// Reads until the end, but not more than one byte over the limit, which is enough to tell data at the limit from larger one.
+pub(crate) fn read_limited<R: Read>(mut reader: R, limit: Option<u64>) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match limit {
+        Some(limit) => reader.take(limit.saturating_add(1)).read_to_end(&mut data)?,
+        None => reader.read_to_end(&mut data)?,
+    };
+    Ok(data)
 }
 
 #[cfg(test)]
@@ -479,14 +458,14 @@ mod tests {
         let scene = Scene {
             version: 1,
             render_options: Some(RenderOptions {
-                camera_id: 0,
+                camera_id: 1,
                 width: 1000,
                 height: 1000,
                 custom_properties: Vec::new(),
             }),
             scene_objects: vec![
                 SceneObject {
-                    id: 0,
+                    id: 2,
                     transform: Some(Transform {
                         parent_id: 0,
                         position: Some(Vector3 {
@@ -515,7 +494,7 @@ mod tests {
             ],
             cameras: vec![
                 Camera {
-                    id: 0,
+                    id: 1,
                     transform: Some(Transform {
                         parent_id: 0,
                         position: Some(Vector3 {
@@ -543,9 +522,419 @@ mod tests {
 
         let read_result_json = read("example_json.cowscene").unwrap();
         println!("Camera X is {} when reading json", read_result_json.cameras[0].transform.as_ref().unwrap().position.as_ref().unwrap().x);
+
+        assert_eq!(read_result_binary, read_result_json);
+    }
+
+    #[test]
+    fn format_detection() {
+        assert_eq!(Format::Json, detect_format(&std::fs::read("./examples/1.cowscene").unwrap()));
+        assert_eq!(Format::Json, detect_format(b"\xEF\xBB\xBF \r\n\t{}"));
+        assert_eq!(Format::Binary, detect_format(&std::fs::read("../file_samples/binary.cowscene").unwrap()));
+        assert_eq!(Format::Binary, detect_format(b""));
+    }
+
+    #[test]
+    fn decode_as_format() {
+        let json = std::fs::read("./examples/11.cowscene").unwrap();
+        let scene = decode_as(&json, Format::Json).unwrap();
+
+        assert_eq!(scene, decode_as(&encode(&scene).unwrap(), Format::Binary).unwrap());
+        assert!(matches!(decode_as(&json, Format::Binary), Err(SceneIOError::FailedToDecode { .. })));
+    }
+
+    #[test]
+    fn binary_envelope() {
+        let scene = decode(&std::fs::read("./examples/11.cowscene").unwrap()).unwrap();
+        let enveloped = encode_with_envelope(&scene).unwrap();
+
+        assert!(enveloped.starts_with(envelope::MAGIC));
+        assert_eq!(Format::Binary, detect_format(&enveloped));
+        assert_eq!(scene, decode(&enveloped).unwrap());
+        assert_eq!(scene, decode_as(&encode(&scene).unwrap(), Format::Binary).unwrap());
+
+        let mut future_version = enveloped.clone();
+        future_version[envelope::MAGIC.len()] = 2;
+        assert_eq!(SceneIOError::FailedToDecode {
+            description: "unsupported binary scene envelope version = 2".to_string(),
+        }.to_string(), decode_as(&future_version, Format::Binary).unwrap_err().to_string());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_files() {
+        let scene = read("./examples/5.cowscene").unwrap();
+        let options = SaveOptions {
+            assets_directory: Some(PathBuf::from("./examples")),
+            ..SaveOptions::default()
+        };
+
+        for name in &["sceneformat_compressed.cowscene.gz", "sceneformat_compressed.cowscene.zst"] {
+            let path = env::temp_dir().join(name);
+            let path = path.to_str().unwrap();
+
+            save_with(&scene, path, &options).unwrap();
+            assert_eq!(cow_mesh(&scene).obj, cow_mesh(&read(path).unwrap()).obj);
+            save_json_with(&scene, path, &options).unwrap();
+            assert_eq!(cow_mesh(&scene).obj, cow_mesh(&read(path).unwrap()).obj);
+
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn legacy_binary_file() {
+        let scene = decode(&std::fs::read("../file_samples/binary.cowscene").unwrap()).unwrap();
+        assert_eq!(Some(camera::Camera::Perspective(PerspectiveCamera { fov: 60.0 })), scene.cameras[0].camera);
+    }
+
+    #[test]
+    fn unknown_format_reports_both_errors() {
+        let mut corrupt = encode(&decode(&std::fs::read("./examples/11.cowscene").unwrap()).unwrap()).unwrap();
+        corrupt.truncate(corrupt.len() / 2);
+
+        match decode(&corrupt) {
+            Err(SceneIOError::UnknownFormat { json_error, binary_error }) => {
+                assert!(json_error.starts_with("expected value"), "{}", json_error);
+                assert!(binary_error.contains("failed to decode Protobuf message"), "{}", binary_error);
+            },
+            other => panic!("Expected format to be unknown, got instead: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reader_and_writer() {
+        let scene = read(std::path::PathBuf::from("./examples/11.cowscene")).unwrap();
+
+        let mut binary = Vec::new();
+        write_to(&scene, &mut binary).unwrap();
+        assert_eq!(encode(&scene).unwrap(), binary);
+        assert_eq!(scene, read_from(binary.as_slice(), None).unwrap());
+
+        let mut json = Vec::new();
+        write_json_to(&scene, &mut json).unwrap();
+        assert_eq!(encode_json(&scene).unwrap(), json);
+        assert_eq!(scene, read_from(std::io::Cursor::new(json), Some(Path::new("./examples"))).unwrap());
+    }
+
+    #[test]
+    fn save_with_sync() {
+        let scene = read("./examples/11.cowscene").unwrap();
+        let path = env::temp_dir().join("sceneformat_save_with_sync.cowscene");
+        let options = SaveOptions {
+            sync: true,
+            ..SaveOptions::default()
+        };
+
+        save_with(&scene, &path, &options).unwrap();
+        assert_eq!(scene, read(&path).unwrap());
+        save_json_with(&scene, &path, &options).unwrap();
+        assert_eq!(encode_json(&scene).unwrap(), std::fs::read(&path).unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_with_envelope() {
+        let scene = read("./examples/11.cowscene").unwrap();
+        let path = env::temp_dir().join("sceneformat_save_with_envelope.cowscene");
+
+        save(&scene, &path).unwrap();
+        assert_eq!(encode(&scene).unwrap(), std::fs::read(&path).unwrap());
+
+        save_with(&scene, &path, &SaveOptions {
+            envelope: true,
+            ..SaveOptions::default()
+        }).unwrap();
+        assert_eq!(encode_with_envelope(&scene).unwrap(), std::fs::read(&path).unwrap());
+        assert_eq!(scene, read(&path).unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_to_other_directory() {
+        let scene = read("./examples/5.cowscene").unwrap();
+        let path = env::temp_dir().join("sceneformat_save_to_other_directory.cowscene");
+
+        // read resolves references against the directory of the scene file, save makes them relative to the new one
+        save(&scene, &path).unwrap();
+        let saved = read_from(File::open(&path).unwrap(), None).unwrap();
+        assert!(Path::new(&cow_mesh(&saved).reference).is_relative());
+        assert_eq!(Path::new("./examples/assets/cow.obj").canonicalize().unwrap(),
+            cow_mesh(&saved).resolved_path(&env::temp_dir()).unwrap().canonicalize().unwrap());
+        assert_eq!(cow_mesh(&scene).obj, cow_mesh(&read(&path).unwrap()).obj);
+
+        // scenes not read from a file keep relative references, unless the directory they are relative to is given
+        let scene = read_from(File::open("./examples/5.cowscene").unwrap(), None).unwrap();
+        save(&scene, &path).unwrap();
+        assert_eq!("assets/cow.obj", cow_mesh(&read_from(File::open(&path).unwrap(), None).unwrap()).reference);
+        save_with(&scene, &path, &SaveOptions {
+            assets_directory: Some(PathBuf::from("./examples")),
+            ..SaveOptions::default()
+        }).unwrap();
+        let saved = read(&path).unwrap();
+        assert_eq!(Path::new("./examples/assets/cow.obj").canonicalize().unwrap(), PathBuf::from(&cow_mesh(&saved).reference).canonicalize().unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn meshes_from_asset_resolver() {
+        let json = r#"{
+            "version": 1,
+            "render_options": { "camera_id": 10 },
+            "cameras": [{ "id": 10 }],
+            "scene_objects": [{ "id": 1, "material_id": "", "meshed_object": { "reference": "triangle.obj" } }]
+        }"#;
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+        let options = ReadOptions {
+            asset_resolver: Some(Arc::new(assets::MemoryResolver::new().with("triangle.obj", obj))),
+            ..ReadOptions::default()
+        };
+
+        let scene = decode_with(json.as_bytes(), &options).unwrap();
+
+        let meshed_object = match &scene.scene_objects[0].mesh {
+            Some(scene_object::Mesh::MeshedObject(meshed_object)) => meshed_object,
+            other => panic!("unexpected mesh: {:?}", other),
+        };
+        assert_eq!("triangle.obj", meshed_object.reference);
+        let geometry = meshed_object.obj.as_ref().unwrap();
+        assert_eq!(3, geometry.vertices.len());
+        assert_eq!(1, geometry.faces.len());
+
+        assert!(matches!(decode_with(json.as_bytes(), &ReadOptions {
+            asset_resolver: Some(Arc::new(assets::MemoryResolver::new())),
+            ..ReadOptions::default()
+        }), Err(SceneIOError::AssetNotFound { .. })));
+    }
+
+    #[test]
+    fn materials_from_mtl_files() {
+        let json = r#"{
+            "version": 1,
+            "render_options": { "camera_id": 10 },
+            "cameras": [{ "id": 10 }],
+            "scene_objects": [
+                { "id": 1, "meshed_object": { "reference": "meshes/red.obj" } },
+                { "id": 2, "material_id": "white", "meshed_object": { "reference": "meshes/mixed.obj" } }
+            ],
+            "materials": [{ "id": "white", "lambert_reflection": { "color": { "r": 1, "g": 1, "b": 1 } } }]
+        }"#;
+        let resolver = assets::MemoryResolver::new()
+            .with("meshes/colors.mtl", "newmtl red\nKd 1 0 0\nnewmtl white\nKd 0.9 0.9 0.9\nnewmtl glass\nd 0.5\nNi 1.33\n")
+            .with("meshes/red.obj", "mtllib colors.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n")
+            .with("meshes/mixed.obj", "mtllib colors.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl white\nf 1 2 3\nusemtl glass\nf 1 2 3\n");
+        let options = ReadOptions {
+            asset_resolver: Some(Arc::new(resolver)),
+            import_materials: true,
+            ..ReadOptions::default()
+        };
+
+        let (scene, eager_face_materials) = decode_with_face_materials(json.as_bytes(), &options).unwrap();
+
+        assert_eq!(vec!["white", "red", "material_1", "glass"], scene.materials.iter().map(|material| material.id.as_str()).collect::<Vec<_>>());
+        assert_eq!(Some(scene_object::ObjectMaterial::MaterialId("red".to_string())), scene.scene_objects[0].object_material);
+        assert_eq!(Some(scene_object::ObjectMaterial::MaterialId("white".to_string())), scene.scene_objects[1].object_material);
+        assert_eq!(Some(material::Material::SpecularReflection(SpecularReflectionMaterial { eta: 1.33 })), scene.materials[3].material);
+
+        let mut lazy = decode_with(json.as_bytes(), &ReadOptions {
+            mesh_loading: MeshLoading::Lazy,
+            validation: Validation::Skip,
+            ..options.clone()
+        }).unwrap();
+        let face_materials = load_meshes(&mut lazy, &options).unwrap();
+        assert_eq!(scene.materials, lazy.materials);
+        assert_eq!(vec![Some("red".to_string())], face_materials[&1]);
+        assert_eq!(vec![Some("material_1".to_string()), Some("glass".to_string())], face_materials[&2]);
+        assert_eq!(face_materials, eager_face_materials);
+
+        let without_import = decode_with(json.as_bytes(), &ReadOptions {
+            asset_resolver: options.asset_resolver,
+            validation: Validation::Skip,
+            ..ReadOptions::default()
+        }).unwrap();
+        assert_eq!(1, without_import.materials.len());
+        assert_eq!(None, without_import.scene_objects[0].object_material);
+    }
+
+    fn cow_mesh(scene: &Scene) -> &MeshedObject {
+        match &scene.scene_objects[0].mesh {
+            Some(scene_object::Mesh::MeshedObject(meshed_object)) => meshed_object,
+            other => panic!("unexpected mesh: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_with_mesh_loading() {
+        let eager = read_with("./examples/5.cowscene", &ReadOptions::default()).unwrap();
+        assert!(cow_mesh(&eager).obj.is_some());
+
+        let never = read_with("./examples/5.cowscene", &ReadOptions {
+            mesh_loading: MeshLoading::Never,
+            ..ReadOptions::default()
+        }).unwrap();
+        assert_eq!(None, cow_mesh(&never).obj);
+
+        let options = ReadOptions {
+            mesh_loading: MeshLoading::Lazy,
+            asset_resolver: Some(Arc::new(FileSystemResolver::new("./examples"))),
+            ..ReadOptions::default()
+        };
+        let mut lazy = read_with("./examples/5.cowscene", &options).unwrap();
+        assert_eq!(None, cow_mesh(&lazy).obj);
+        assert!(matches!(load_meshes(&mut lazy, &ReadOptions {
+            asset_resolver: None,
+            ..options.clone()
+        }), Err(SceneIOError::NoAssetResolver)));
+        load_meshes(&mut lazy, &options).unwrap();
+        assert_eq!(cow_mesh(&eager).obj, cow_mesh(&lazy).obj);
+    }
+
+    #[test]
+    fn read_with_validation_and_defaults() {
+        let json = std::fs::read_to_string("./examples/5.cowscene").unwrap().replace("\"cameraId\": 1", "\"cameraId\": 7");
+        let options = ReadOptions {
+            mesh_loading: MeshLoading::Never,
+            ..ReadOptions::default()
+        };
+
+        assert!(matches!(decode_with(json.as_bytes(), &options), Err(SceneIOError::InvalidScene { .. })));
+
+        let scene = decode_with(json.as_bytes(), &ReadOptions {
+            validation: Validation::Warn,
+            apply_defaults: false,
+            ..options
+        }).unwrap();
+        assert_eq!(None, scene.cameras[0].transform.as_ref().unwrap().scale);
+
+        let skipped = decode_with(json.as_bytes(), &ReadOptions {
+            validation: Validation::Skip,
+            ..ReadOptions::default()
+        }).unwrap();
+        assert_eq!(7, skipped.render_options.unwrap().camera_id);
+    }
+
+    #[test]
+    fn read_with_size_limits() {
+        let scene_size = std::fs::metadata("./examples/5.cowscene").unwrap().len();
+
+        assert!(read_with("./examples/5.cowscene", &ReadOptions {
+            max_scene_size: Some(scene_size),
+            ..ReadOptions::default()
+        }).is_ok());
+
+        assert_eq!(format!("Scene is larger than the limit of {} bytes", scene_size - 1), read_with("./examples/5.cowscene", &ReadOptions {
+            max_scene_size: Some(scene_size - 1),
+            ..ReadOptions::default()
+        }).unwrap_err().to_string());
+
+        assert!(matches!(read_with("./examples/5.cowscene", &ReadOptions {
+            max_asset_size: Some(1024),
+            ..ReadOptions::default()
+        }), Err(SceneIOError::TooLarge { .. })));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decompressed_size_limit() {
+        let data = std::fs::read("./examples/5.cowscene").unwrap();
+        let compressed = compression::compress(data.clone(), Compression::Zstd).unwrap();
+
+        let limit = compressed.len() as u64;
+        assert!(limit < data.len() as u64);
+
+        assert_eq!(format!("Decompressed scene is larger than the limit of {} bytes", limit), decode_with(&compressed, &ReadOptions {
+            max_scene_size: Some(limit),
+            ..ReadOptions::default()
+        }).unwrap_err().to_string());
+        assert!(decode_with(&compressed, &ReadOptions {
+            max_scene_size: Some(data.len() as u64),
+            mesh_loading: MeshLoading::Never,
+            ..ReadOptions::default()
+        }).is_ok());
+    }
+
+    #[test]
+    fn java_json_fixture() {
+        let scene = decode(&std::fs::read("../java/example/example_json.cowscene").unwrap()).unwrap();
+        let binary_scene = decode(&std::fs::read("../java/example/example_binary.cowscene").unwrap()).unwrap();
+
+        assert_eq!(binary_scene.cameras, scene.cameras);
+        assert_eq!(Some(camera::Camera::Perspective(PerspectiveCamera { fov: 60.0 })), scene.cameras[0].camera);
+        let position = scene.cameras[0].transform.as_ref().unwrap().position.as_ref().unwrap();
+        assert!((1.01 - position.x).abs() < DELTA);
+
+        assert_eq!(0, scene.scene_objects[0].id);
+        assert_eq!(Some(scene_object::Mesh::MeshedObject(MeshedObject::default())), scene.scene_objects[0].mesh);
+        // "solid" material was removed from the format
+        assert_eq!(Some(scene_object::ObjectMaterial::Material(Material::default())), scene.scene_objects[0].object_material);
+    }
+
+    #[test]
+    fn simple_json_file_sample() {
+        let scene = decode(&std::fs::read("../file_samples/simple_json.cowscene").unwrap()).unwrap();
+
+        assert_eq!(1920, scene.render_options.as_ref().unwrap().width);
+        assert_eq!(vec![2, 3], scene.lights.iter().map(|light| light.id).collect::<Vec<_>>());
+        assert_eq!(Some(light::Light::Point(LightPoint {})), scene.lights[0].light);
+        assert_eq!(Some(scene_object::Mesh::MeshedObject(MeshedObject {
+            reference: "cow2.obj".to_string(),
+            obj: None,
+        })), scene.scene_objects[0].mesh);
+    }
+
+    #[test]
+    fn special_floats_in_json() {
+        let mut scene = read("./examples/11.cowscene").unwrap();
+        scene.scene_objects[0].mesh = Some(scene_object::Mesh::Sphere(Sphere { radius: f64::NAN }));
+        scene.cameras[0].transform.as_mut().unwrap().position = Some(Vector3 { x: f64::INFINITY, y: f64::NEG_INFINITY, z: 0.0 });
+
+        let json = String::from_utf8(encode_json(&scene).unwrap()).unwrap();
+        assert!(json.contains("\"radius\": \"NaN\""), "{}", json);
+        assert!(json.contains("\"x\": \"Infinity\""), "{}", json);
+        assert!(json.contains("\"y\": \"-Infinity\""), "{}", json);
+
+        let decoded = decode(json.as_bytes()).unwrap();
+        match &decoded.scene_objects[0].mesh {
+            Some(scene_object::Mesh::Sphere(sphere)) => assert!(sphere.radius.is_nan()),
+            other => panic!("unexpected mesh: {:?}", other),
+        }
+        assert_eq!(scene.cameras, decoded.cameras);
+    }
+
+    #[test]
+    fn canonical_proto_json() {
+        let canonical = r#"{
+            "version": "1",
+            "render_options": {"cameraId": "1", "width": 640, "height": "360"},
+            "sceneObjects": [
+                {"id": 2, "materialId": "red", "meshedObject": {"reference": "examples/assets/cow.obj"}},
+                {"id": 3, "material": {"specularReflection": {"eta": "1.5"}}, "sphere": {"radius": "NaN"}}
+            ],
+            "cameras": [{"id": 1, "perspective": {"fov": 60}}],
+            "materials": [{"id": "red", "lambertReflection": {"color": {"r": 1, "g": "0", "b": 0}}}]
+        }"#;
+
+        let scene = decode(canonical.as_bytes()).unwrap();
+
+        assert_eq!(1, scene.version);
+        assert_eq!(360, scene.render_options.as_ref().unwrap().height);
+        assert_eq!(Some(scene_object::ObjectMaterial::MaterialId("red".to_string())), scene.scene_objects[0].object_material);
+        assert_eq!(Some(scene_object::ObjectMaterial::Material(Material {
+            id: "".to_string(),
+            material: Some(material::Material::SpecularReflection(SpecularReflectionMaterial { eta: 1.5 })),
+        })), scene.scene_objects[1].object_material);
+        match &scene.scene_objects[1].mesh {
+            Some(scene_object::Mesh::Sphere(sphere)) => assert!(sphere.radius.is_nan()),
+            other => panic!("Expected sphere, got instead: {:?}", other),
+        }
+        assert_eq!(Some(camera::Camera::Perspective(PerspectiveCamera { fov: 60.0 })), scene.cameras[0].camera);
     }
 
     #[test]
+    #[allow(clippy::get_first)]
     fn example_from_docs1() {
         let result = read("./examples/1.cowscene").unwrap();
 
@@ -593,12 +982,14 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::get_first)]
     fn example_from_docs5() {
         let result = read("./examples/5.cowscene").unwrap();
 
         if let Some(meshed_object) = result.scene_objects.get(0).unwrap().mesh.as_ref() {
             if let scene_object::Mesh::MeshedObject(meshed_object) = meshed_object {
-                assert_eq!("./examples/assets/cow.obj", meshed_object.reference);
+                assert_eq!(env::current_dir().unwrap().join("examples/assets/cow.obj").to_string_lossy(), meshed_object.reference);
+                assert_eq!(Some(env::current_dir().unwrap().join("examples/assets/cow.obj")), meshed_object.resolved_path(Path::new("./examples")));
 
                 let obj = meshed_object.obj.as_ref().unwrap();
 
@@ -662,6 +1053,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::get_first)]
     fn example_from_docs13() {
         let scene = read("./examples/13.cowscene").unwrap();
 
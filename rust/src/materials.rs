@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::MaterialError;
+use crate::index::SceneIndex;
+use crate::obj::ObjMesh;
+use crate::{scene_object, Material, Scene, SceneObject};
+
+/// Material of a scene object, whether it is specified inline or referenced by id.
+pub fn resolved_material<'a>(scene_object: &'a SceneObject, scene: &'a Scene) -> Result<&'a Material, MaterialError> {
+    match &scene_object.object_material {
+        Some(scene_object::ObjectMaterial::Material(material)) => Ok(material),
+        Some(scene_object::ObjectMaterial::MaterialId(material_id)) => scene.materials.iter()
+            .find(|material| &material.id == material_id)
+            .ok_or_else(|| unknown_material(scene_object, material_id)),
+        None => Err(MaterialError::NotSet { object_id: scene_object.id }),
+    }
+}
+
+impl<'a> SceneIndex<'a> {
+
+    /// Same as `resolved_material`, but without scanning `Scene.materials`.
+    pub fn resolved_material(&self, scene_object: &'a SceneObject) -> Result<&'a Material, MaterialError> {
+        match &scene_object.object_material {
+            Some(scene_object::ObjectMaterial::Material(material)) => Ok(material),
+            Some(scene_object::ObjectMaterial::MaterialId(material_id)) => self.material(material_id)
+                .ok_or_else(|| unknown_material(scene_object, material_id)),
+            None => Err(MaterialError::NotSet { object_id: scene_object.id }),
+        }
+    }
+}
+
+fn unknown_material(scene_object: &SceneObject, material_id: &str) -> MaterialError {
+    MaterialError::UnknownMaterial {
+        object_id: scene_object.id,
+        material_id: material_id.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialForm {
+    /// Every scene object with a material has it inline, `Scene.materials` is empty.
+    Inline,
+    /// Every scene object with a material references it in `Scene.materials` by id.
+    /// Materials with the same properties are stored once.
+    Referenced,
+}
+
+/// Converts materials of all scene objects to the same form.
+pub fn canonicalize_materials(scene: &mut Scene, form: MaterialForm) -> Result<(), MaterialError> {
+    match form {
+        MaterialForm::Inline => inline_materials(scene),
+        MaterialForm::Referenced => {
+            reference_materials(scene);
+            Ok(())
+        },
+    }
+}
+
+fn inline_materials(scene: &mut Scene) -> Result<(), MaterialError> {
+    let mut inlined = Vec::with_capacity(scene.scene_objects.len());
+    for scene_object in &scene.scene_objects {
+        inlined.push(match scene_object.object_material {
+            Some(_) => Some(resolved_material(scene_object, scene)?.clone()),
+            None => None,
+        });
+    }
+
+    for (scene_object, material) in scene.scene_objects.iter_mut().zip(inlined) {
+        if let Some(material) = material {
+            scene_object.object_material = Some(scene_object::ObjectMaterial::Material(material));
+        }
+    }
+    scene.materials.clear();
+
+    Ok(())
+}
+
+fn reference_materials(scene: &mut Scene) {
+    let mut library: Vec<Material> = Vec::new();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let mut used_ids: HashSet<String> = scene.materials.iter().map(|material| material.id.clone()).collect();
+
+    for material in scene.materials.drain(..) {
+        let id = material.id.clone();
+        let library_id = add_to_library(&mut library, material, &mut used_ids);
+        renamed.entry(id).or_insert(library_id);
+    }
+
+    for scene_object in &mut scene.scene_objects {
+        let material_id = match scene_object.object_material.take() {
+            Some(scene_object::ObjectMaterial::MaterialId(material_id)) => renamed.get(&material_id).cloned().unwrap_or(material_id),
+            Some(scene_object::ObjectMaterial::Material(material)) => add_to_library(&mut library, material, &mut used_ids),
+            None => continue,
+        };
+        scene_object.object_material = Some(scene_object::ObjectMaterial::MaterialId(material_id));
+    }
+
+    scene.materials = library;
+}
+
+/// Library material ids of the faces of meshed objects by scene object id, as returned by `load_meshes`.
+/// `None` for the faces without a material in the obj file.
+pub type FaceMaterials = HashMap<i32, Vec<Option<String>>>;
+
+/// Adds materials used by the faces of obj mesh to the library and returns their ids for each of the faces.
+/// The scene object gets the material if it has none and all of its faces use the same one.
+pub(crate) fn import_obj_materials(library: &mut Vec<Material>, object_material: &mut Option<scene_object::ObjectMaterial>, mesh: &ObjMesh) -> Vec<Option<String>> {
+    let mut used_ids: HashSet<String> = library.iter().map(|material| material.id.clone()).collect();
+    let mut library_ids: HashMap<usize, String> = HashMap::new();
+
+    for index in mesh.face_materials.iter().flatten() {
+        if !library_ids.contains_key(index) {
+            let id = add_to_library(library, mesh.materials[*index].to_material(), &mut used_ids);
+            library_ids.insert(*index, id);
+        }
+    }
+
+    if object_material.is_none() {
+        if let Some(Some(first)) = mesh.face_materials.first() {
+            if mesh.face_materials.iter().all(|index| *index == Some(*first)) {
+                *object_material = Some(scene_object::ObjectMaterial::MaterialId(library_ids[first].clone()));
+            }
+        }
+    }
+
+    mesh.face_materials.iter()
+        .map(|index| index.map(|index| library_ids[&index].clone()))
+        .collect()
+}
+
+/// Returns id of a library material with the same properties, adding the material if there is none.
+pub(crate) fn add_to_library(library: &mut Vec<Material>, mut material: Material, used_ids: &mut HashSet<String>) -> String {
+    if let Some(existing) = library.iter().find(|existing| existing.material == material.material) {
+        return existing.id.clone();
+    }
+
+    if material.id.is_empty() || library.iter().any(|existing| existing.id == material.id) {
+        material.id = (1..).map(|n| format!("material_{}", n))
+            .find(|id| !used_ids.contains(id))
+            .unwrap();
+    }
+    used_ids.insert(material.id.clone());
+
+    let id = material.id.clone();
+    library.push(material);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{material, Color, LambertReflectionMaterial, SpecularReflectionMaterial};
+
+    fn lambert(id: &str, r: f64) -> Material {
+        Material {
+            id: id.to_string(),
+            material: Some(material::Material::LambertReflection(LambertReflectionMaterial {
+                color: Some(Color {
+                    r,
+                    g: 0.0,
+                    b: 0.0,
+                }),
+            })),
+        }
+    }
+
+    fn scene_with_mixed_materials() -> Scene {
+        let mut scene = crate::read("./examples/11.cowscene").unwrap();
+        scene.materials = vec![lambert("red", 1.0), lambert("also_red", 1.0)];
+        scene.scene_objects[0].object_material = Some(scene_object::ObjectMaterial::MaterialId("also_red".to_string()));
+        scene.scene_objects[2].object_material = Some(scene_object::ObjectMaterial::Material(lambert("", 1.0)));
+        scene
+    }
+
+    #[test]
+    fn resolves_inline_and_referenced_materials() {
+        let scene = scene_with_mixed_materials();
+        let index = SceneIndex::new(&scene);
+
+        assert_eq!(Ok(&scene.materials[1]), resolved_material(&scene.scene_objects[0], &scene));
+        assert_eq!(Ok(&scene.materials[1]), index.resolved_material(&scene.scene_objects[0]));
+        assert_eq!(Ok(&lambert("", 1.0)), resolved_material(&scene.scene_objects[2], &scene));
+
+        let mut scene_object = scene.scene_objects[0].clone();
+        scene_object.object_material = Some(scene_object::ObjectMaterial::MaterialId("blue".to_string()));
+        assert_eq!(Err(MaterialError::UnknownMaterial { object_id: 2, material_id: "blue".to_string() }), resolved_material(&scene_object, &scene));
+
+        scene_object.object_material = None;
+        assert_eq!(Err(MaterialError::NotSet { object_id: 2 }), index.resolved_material(&scene_object));
+    }
+
+    #[test]
+    fn canonicalize_inline() {
+        let mut scene = scene_with_mixed_materials();
+
+        canonicalize_materials(&mut scene, MaterialForm::Inline).unwrap();
+
+        assert!(scene.materials.is_empty());
+        assert_eq!(Some(scene_object::ObjectMaterial::Material(lambert("also_red", 1.0))), scene.scene_objects[0].object_material);
+        assert_eq!(Some(scene_object::ObjectMaterial::Material(lambert("", 1.0))), scene.scene_objects[2].object_material);
+    }
+
+    #[test]
+    fn canonicalize_inline_without_material() {
+        let mut scene = scene_with_mixed_materials();
+        scene.scene_objects[1].object_material = None;
+
+        canonicalize_materials(&mut scene, MaterialForm::Inline).unwrap();
+
+        assert!(scene.materials.is_empty());
+        assert_eq!(None, scene.scene_objects[1].object_material);
+        assert_eq!(Some(scene_object::ObjectMaterial::Material(lambert("also_red", 1.0))), scene.scene_objects[0].object_material);
+    }
+
+    #[test]
+    fn canonicalize_referenced() {
+        let mut scene = scene_with_mixed_materials();
+
+        canonicalize_materials(&mut scene, MaterialForm::Referenced).unwrap();
+
+        let specular = Material {
+            id: "material_1".to_string(),
+            material: Some(material::Material::SpecularReflection(SpecularReflectionMaterial {
+                eta: 2.0,
+            })),
+        };
+        assert_eq!(vec![lambert("red", 1.0), specular], scene.materials);
+
+        let material_ids: Vec<_> = scene.scene_objects.iter().map(|o| o.object_material.clone().unwrap()).collect();
+        assert_eq!(vec![
+            scene_object::ObjectMaterial::MaterialId("red".to_string()),
+            scene_object::ObjectMaterial::MaterialId("material_1".to_string()),
+            scene_object::ObjectMaterial::MaterialId("red".to_string()),
+        ], material_ids);
+        assert_eq!(Ok(()), crate::validate(&scene));
+    }
+}
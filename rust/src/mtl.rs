@@ -0,0 +1,222 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::errors::{ObjError, SceneIOError};
+use crate::obj::{parse_lines, Line};
+use crate::{material, Color, LambertReflectionMaterial, Material, SpecularReflectionMaterial};
+
+const DEFAULT_ETA: f64 = 1.5;
+
+/// Material defined with `newmtl` in an mtl file. Properties are `None` if they are not set in the file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Option<Color>,
+    /// `Ks`
+    pub specular: Option<Color>,
+    /// `Ns`
+    pub specular_exponent: Option<f64>,
+    /// `Ni`
+    pub optical_density: Option<f64>,
+    /// `d`, 1 is opaque.
+    pub dissolve: Option<f64>,
+    /// `illum`
+    pub illumination_model: Option<i32>,
+    /// `map_Kd`
+    pub diffuse_map: Option<String>,
+}
+
+impl MtlMaterial {
+
+    /// Transparent materials and the ones with reflection or refraction illumination models (3, 4, 6, 7 and 9) become
+    /// `SpecularReflectionMaterial` with `Ni` as index of refraction, the others become `LambertReflectionMaterial`
+    /// with `Kd` as color. `Ks`, `Ns` and `map_Kd` have no counterpart in the scene format, a warning is logged if they are set.
+    pub fn to_material(&self) -> Material {
+        let transparent = self.dissolve.is_some_and(|dissolve| dissolve < 1.0);
+        let specular = matches!(self.illumination_model, Some(3) | Some(4) | Some(6) | Some(7) | Some(9));
+
+        let dropped = self.dropped_properties();
+        if !dropped.is_empty() {
+            warn!("Material {} sets {}, which can not be expressed in the scene format, ignoring...", self.name, dropped.join(", "));
+        }
+
+        let material = if transparent || specular {
+            material::Material::SpecularReflection(SpecularReflectionMaterial {
+                eta: self.optical_density.filter(|eta| *eta > 0.0).unwrap_or(DEFAULT_ETA),
+            })
+        } else {
+            material::Material::LambertReflection(LambertReflectionMaterial {
+                color: Some(self.diffuse.clone().unwrap_or(Color {
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                })),
+            })
+        };
+
+        Material {
+            id: self.name.clone(),
+            material: Some(material),
+        }
+    }
+
+    /// Keywords of the properties which are set, but are lost in `to_material`.
+    pub fn dropped_properties(&self) -> Vec<&'static str> {
+        let mut dropped = Vec::new();
+        if self.specular.is_some() {
+            dropped.push("Ks");
+        }
+        if self.specular_exponent.is_some() {
+            dropped.push("Ns");
+        }
+        if self.diffuse_map.is_some() {
+            dropped.push("map_Kd");
+        }
+        dropped
+    }
+}
+
+pub fn read_mtl<R: BufRead>(reader: R) -> Result<Vec<MtlMaterial>, SceneIOError> {
+    Ok(read_mtl_at(reader, None)?)
+}
+
+/// Same as `read_mtl`, with the path of the file in errors.
+pub(crate) fn read_mtl_at<R: BufRead>(reader: R, path: Option<&Path>) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    parse_lines(reader, path, |line| parse_line(line, &mut materials))?;
+    Ok(materials)
+}
+
+fn parse_line(line: &Line, materials: &mut Vec<MtlMaterial>) -> Result<(), ObjError> {
+    let keyword = match line.tokens.first() {
+        Some(keyword) => keyword.text,
+        None => return Ok(()),
+    };
+
+    if keyword == "newmtl" {
+        materials.push(MtlMaterial {
+            name: line.rest().ok_or_else(|| line.missing("material name"))?.to_string(),
+            ..MtlMaterial::default()
+        });
+        return Ok(());
+    }
+
+    let material = match materials.last_mut() {
+        Some(material) => material,
+        None => {
+            warn!("Material property at line {} is set before newmtl, ignoring...", line.number);
+            return Ok(());
+        },
+    };
+
+    match keyword {
+        "Kd" => material.diffuse = Some(parse_color(line, "Kd")?),
+        "Ks" => material.specular = Some(parse_color(line, "Ks")?),
+        "Ns" => material.specular_exponent = Some(line.number(0, "Ns")?),
+        "Ni" => material.optical_density = Some(line.number(0, "Ni")?),
+        "d" => material.dissolve = Some(line.number(0, "d")?),
+        "illum" => material.illumination_model = Some(line.number(0, "illum")?),
+        // the file name comes after the options
+        "map_Kd" => material.diffuse_map = Some(line.arguments().last().ok_or_else(|| line.missing("texture file"))?.text.to_string()),
+        other => {
+            debug!("Unsupported mtl statement at line {}: {}, ignoring...", line.number, other);
+        }
+    }
+
+    Ok(())
+}
+
+/// `r [g b]`, g and b are equal to r if they are not set.
+fn parse_color(line: &Line, name: &'static str) -> Result<Color, ObjError> {
+    let r = line.number(0, name)?;
+
+    Ok(Color {
+        r,
+        g: line.optional_number(1, name, r)?,
+        b: line.optional_number(2, name, r)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const MTL: &str = "# Blender MTL File\n\
+        newmtl Red Paint\n\
+        Ns 250.0\n\
+        Ka 1.0 1.0 1.0\n\
+        Kd 0.8 0.1 0.1\n\
+        Ks 0.5\n\
+        Ni 1.45\n\
+        d 1.0\n\
+        illum 2\n\
+        map_Kd -s 1 1 1 textures/paint.png\n\
+        \n\
+        newmtl Glass\n\
+        Kd 1 1 1\n\
+        Ni 1.5\n\
+        d 0.2\n\
+        illum 4\n";
+
+    #[test]
+    fn parses_materials() {
+        let materials = read_mtl(MTL.as_bytes()).unwrap();
+
+        assert_eq!(MtlMaterial {
+            name: "Red Paint".to_string(),
+            diffuse: Some(Color { r: 0.8, g: 0.1, b: 0.1 }),
+            specular: Some(Color { r: 0.5, g: 0.5, b: 0.5 }),
+            specular_exponent: Some(250.0),
+            optical_density: Some(1.45),
+            dissolve: Some(1.0),
+            illumination_model: Some(2),
+            diffuse_map: Some("textures/paint.png".to_string()),
+        }, materials[0]);
+        assert_eq!(2, materials.len());
+    }
+
+    #[test]
+    fn maps_to_scene_materials() {
+        let materials = read_mtl(MTL.as_bytes()).unwrap();
+
+        assert_eq!(Material {
+            id: "Red Paint".to_string(),
+            material: Some(material::Material::LambertReflection(LambertReflectionMaterial {
+                color: Some(Color { r: 0.8, g: 0.1, b: 0.1 }),
+            })),
+        }, materials[0].to_material());
+        assert_eq!(Material {
+            id: "Glass".to_string(),
+            material: Some(material::Material::SpecularReflection(SpecularReflectionMaterial {
+                eta: 1.5,
+            })),
+        }, materials[1].to_material());
+    }
+
+    #[test]
+    fn maps_reflection_to_specular_material() {
+        let materials = read_mtl("newmtl Mirror\nKd 0.2 0.2 0.2\nillum 3\n".as_bytes()).unwrap();
+
+        assert_eq!(Some(material::Material::SpecularReflection(SpecularReflectionMaterial {
+            eta: 1.5,
+        })), materials[0].to_material().material);
+    }
+
+    #[test]
+    fn reports_dropped_properties() {
+        let materials = read_mtl(MTL.as_bytes()).unwrap();
+        let specular_only = read_mtl("newmtl Shiny\nKs 1 1 1\nNs 900\n".as_bytes()).unwrap();
+
+        assert_eq!(vec!["Ks", "Ns", "map_Kd"], materials[0].dropped_properties());
+        assert_eq!(Vec::<&str>::new(), materials[1].dropped_properties());
+        assert_eq!(vec!["Ks", "Ns"], specular_only[0].dropped_properties());
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert_eq!("Failed to read obj file: line 2, column 4: Failed to parse Kd: invalid float literal in \"Kd red\"",
+            read_mtl("newmtl a\nKd red\n".as_bytes()).unwrap_err().to_string());
+    }
+}
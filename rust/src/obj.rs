@@ -1,106 +1,352 @@
+use std::fmt::Display;
 use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
 
-use crate::errors::SceneIOError;
-use crate::{MeshGeometry, Face, FaceElement, VertexNormal, Vertex};
+use crate::assets::AssetResolver;
+use crate::errors::{ObjError, ObjErrorKind, SceneIOError};
+use crate::mtl::{self, MtlMaterial};
+use crate::{MeshGeometry, Face, FaceElement, TextureCoordinates, VertexNormal, Vertex};
 use std::io::{BufReader, BufRead};
 
-pub fn read_obj_file(path: &str) -> Result<MeshGeometry, SceneIOError> {
-    let mut faces = Vec::new();
-    let texture_coordinates = Vec::new();
-    let mut vertex_normals = Vec::new();
-    let mut vertices = Vec::new();
-
-    let file = File::open(path).map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to open obj file: {}", err.to_string()),
+pub fn read_obj_file<P: AsRef<Path>>(path: P) -> Result<MeshGeometry, SceneIOError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|source| ObjError {
+        path: Some(path.to_path_buf()),
+        line: 0,
+        column: 0,
+        text: String::new(),
+        source: ObjErrorKind::IOError { source },
     })?;
-    let reader = BufReader::new(file);
+    Ok(read_obj_at(BufReader::new(file), Some(path))?)
+}
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
+pub fn read_obj<R: BufRead>(reader: R) -> Result<MeshGeometry, SceneIOError> {
+    Ok(read_obj_at(reader, None)?)
+}
 
-        let spl: Vec<&str> = line.split(" ").collect();
-        match spl[0] {
-            "#" => {
-                // ignore comments
-            },
-            "v" => vertices.push(parse_vertex(&spl[1..])?),
-            "vn" => vertex_normals.push(parse_vertex_normal(&spl[1..])?),
-            "f" => faces.push(parse_face(&spl[1..])?),
-            "g" => {
-                // ignore
-            },
-            "usemtl" | "mtllib" => {
-                // ignore materials at the moment ...
-            },
-            "o" | "s" => {
-                // ignore...
-            },
-            other => {
-                warn!("Unknown line type in obj: {}, ignoring...", other);
-            }
+/// Same as `read_obj`, with the path of the file in errors.
+pub(crate) fn read_obj_at<R: BufRead>(reader: R, path: Option<&Path>) -> Result<MeshGeometry, ObjError> {
+    Ok(parse_obj(reader, path)?.geometry)
+}
+
+/// Obj geometry with the materials of its faces.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjMesh {
+    pub geometry: MeshGeometry,
+    /// Materials of the mtl files referenced with `mtllib`.
+    pub materials: Vec<MtlMaterial>,
+    /// Index in `materials` for each of the faces, as set with `usemtl`.
+    /// `None` before the first `usemtl` and for materials missing in the libraries.
+    pub face_materials: Vec<Option<usize>>,
+}
+
+/// Reads obj file referenced from a scene, along with the mtl files it references.
+/// The mtl files are read with the asset resolver, relative to the obj reference, each of them up to `max_asset_size`.
+pub fn read_obj_mesh<R: BufRead>(reader: R, reference: &str, asset_resolver: &dyn AssetResolver, max_asset_size: Option<u64>) -> Result<ObjMesh, SceneIOError> {
+    let parsed = parse_obj(reader, Some(Path::new(reference)))?;
+
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    for library in &parsed.material_libraries {
+        let library_reference = sibling_reference(reference, library);
+        match asset_resolver.read_limited(&library_reference, max_asset_size) {
+            Ok(data) => materials.extend(mtl::read_mtl_at(data.as_slice(), Some(Path::new(&library_reference)))?),
+            Err(err @ SceneIOError::TooLarge { .. }) => return Err(err),
+            Err(err) => warn!("Failed to read material library {} of {}, ignoring: {}", library_reference, reference, err),
         }
     }
 
-    Ok(MeshGeometry {
-        faces,
-        texture_coordinates,
-        vertex_normals,
-        vertices,
+    let material_indices: Vec<Option<usize>> = parsed.material_names.iter()
+        .map(|name| {
+            let index = materials.iter().rposition(|material| &material.name == name);
+            // obj files without libraries often name the default material, e.g. `usemtl (null)`
+            if index.is_none() && !parsed.material_libraries.is_empty() {
+                warn!("Material {} used in {} is not defined in its material libraries", name, reference);
+            }
+            index
+        })
+        .collect();
+
+    Ok(ObjMesh {
+        geometry: parsed.geometry,
+        materials,
+        face_materials: parsed.face_materials.iter().map(|name| name.and_then(|name| material_indices[name])).collect(),
     })
 }
 
-fn parse_vertex(parts: &[&str]) -> Result<Vertex, SceneIOError> {
-    let x = parts[0].parse().map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to parse vertex x: {}", err),
-    })?;
-    let y = parts[1].parse().map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to parse vertex y: {}", err),
-    })?;
-    let z = parts[2].parse().map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to parse vertex z: {}", err),
-    })?;
-    let w = if parts.len() >= 4 {
-        parts[3].parse().map_err(|err| SceneIOError::FailedToReadObj {
-            description: format!("Failed to parse vertex w: {}", err),
-        })?
-    } else {
-        1.0
+/// Reference of a file next to the referenced one.
+fn sibling_reference(reference: &str, name: &str) -> String {
+    match reference.rfind(['/', '\\']) {
+        Some(index) => format!("{}/{}", &reference[..index], name),
+        None => name.to_string(),
+    }
+}
+
+#[derive(Default)]
+struct ParsedObj {
+    geometry: MeshGeometry,
+    material_libraries: Vec<String>,
+    /// Names used with `usemtl`, in the order of first use.
+    material_names: Vec<String>,
+    /// Index in `material_names` for each of the faces.
+    face_materials: Vec<Option<usize>>,
+    current_material: Option<usize>,
+}
+
+fn parse_obj<R: BufRead>(reader: R, path: Option<&Path>) -> Result<ParsedObj, ObjError> {
+    let mut parsed = ParsedObj::default();
+    parse_lines(reader, path, |line| parse_line(line, &mut parsed))?;
+    Ok(parsed)
+}
+
+/// Calls `parse` for each line of obj or mtl file.
+pub(crate) fn parse_lines<R: BufRead, F: FnMut(&Line) -> Result<(), ObjError>>(reader: R, path: Option<&Path>, mut parse: F) -> Result<(), ObjError> {
+    // text of a line ending with `\`, continued on the next line
+    let mut continued = String::new();
+    let mut continued_from = 0;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| ObjError {
+            path: path.map(Path::to_path_buf),
+            line: index + 1,
+            column: 0,
+            text: String::new(),
+            source: ObjErrorKind::IOError { source },
+        })?;
+
+        let content = strip_comment(&line).trim_end();
+        if continued.is_empty() {
+            continued_from = index + 1;
+        }
+
+        if let Some(content) = content.strip_suffix('\\') {
+            continued.push_str(content);
+            continued.push(' ');
+            continue;
+        }
+
+        let result = if continued.is_empty() {
+            parse(&Line::new(index + 1, content))
+        } else {
+            continued.push_str(content);
+            let result = parse(&Line::new(continued_from, &continued));
+            continued.clear();
+            result
+        };
+        result.map_err(|err| with_path(err, path))?;
+    }
+
+    if !continued.is_empty() {
+        parse(&Line::new(continued_from, &continued)).map_err(|err| with_path(err, path))?;
+    }
+
+    Ok(())
+}
+
+fn with_path(err: ObjError, path: Option<&Path>) -> ObjError {
+    ObjError {
+        path: path.map(Path::to_path_buf),
+        ..err
+    }
+}
+
+fn parse_line(line: &Line, parsed: &mut ParsedObj) -> Result<(), ObjError> {
+    let geometry = &mut parsed.geometry;
+    let keyword = match line.tokens.first() {
+        Some(keyword) => keyword.text,
+        None => return Ok(()),
     };
 
+    match keyword {
+        "v" => geometry.vertices.push(parse_vertex(line)?),
+        "vt" => geometry.texture_coordinates.push(parse_texture_coordinates(line)?),
+        "vn" => geometry.vertex_normals.push(parse_vertex_normal(line)?),
+        "f" => {
+            let face = parse_face(line, &IndexCounts {
+                vertices: geometry.vertices.len(),
+                texture_coordinates: geometry.texture_coordinates.len(),
+                vertex_normals: geometry.vertex_normals.len(),
+            })?;
+            geometry.faces.push(face);
+            parsed.face_materials.push(parsed.current_material);
+        },
+        "g" => {
+            // ignore
+        },
+        "mtllib" => {
+            parsed.material_libraries.extend(line.arguments().iter().map(|token| token.text.to_string()));
+        },
+        "usemtl" => {
+            let name = line.rest().ok_or_else(|| line.missing("material name"))?;
+            let index = match parsed.material_names.iter().position(|existing| existing == name) {
+                Some(index) => index,
+                None => {
+                    parsed.material_names.push(name.to_string());
+                    parsed.material_names.len() - 1
+                },
+            };
+            parsed.current_material = Some(index);
+        },
+        "o" | "s" => {
+            // ignore...
+        },
+        other => {
+            warn!("Unknown line type in obj at line {}: {}, ignoring...", line.number, other);
+        }
+    }
+
+    Ok(())
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+/// Whitespace separated part of a line, columns are 1-based and counted in characters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Token<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) column: usize,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut column = 0;
+
+    for (index, char) in text.char_indices() {
+        column += 1;
+
+        match (char.is_whitespace(), start) {
+            (true, Some((start_index, start_column))) => {
+                tokens.push(Token { text: &text[start_index..index], column: start_column });
+                start = None;
+            },
+            (false, None) => start = Some((index, column)),
+            _ => {},
+        }
+    }
+
+    if let Some((start_index, start_column)) = start {
+        tokens.push(Token { text: &text[start_index..], column: start_column });
+    }
+
+    tokens
+}
+
+/// Line without comments, lines continued with `\` are joined into one.
+pub(crate) struct Line<'a> {
+    pub(crate) number: usize,
+    text: &'a str,
+    /// Column right after the last character, where missing arguments are reported.
+    end_column: usize,
+    /// Keyword followed by the arguments.
+    pub(crate) tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Line<'a> {
+
+    fn new(number: usize, text: &'a str) -> Line<'a> {
+        Line {
+            number,
+            text,
+            end_column: text.trim_end().chars().count() + 1,
+            tokens: tokenize(text),
+        }
+    }
+
+    pub(crate) fn arguments(&self) -> &[Token<'a>] {
+        &self.tokens[1..]
+    }
+
+    /// Text of the arguments, for names which may contain spaces.
+    pub(crate) fn rest(&self) -> Option<&'a str> {
+        let first = self.arguments().first()?;
+        let start = first.text.as_ptr() as usize - self.text.as_ptr() as usize;
+        Some(self.text[start..].trim_end())
+    }
+
+    pub(crate) fn error(&self, column: usize, kind: ObjErrorKind) -> ObjError {
+        ObjError {
+            path: None,
+            line: self.number,
+            column,
+            text: self.text.trim().to_string(),
+            source: kind,
+        }
+    }
+
+    pub(crate) fn missing(&self, expected: &str) -> ObjError {
+        self.error(self.end_column, ObjErrorKind::TooFewComponents { expected: expected.to_string() })
+    }
+
+    pub(crate) fn number<T: FromStr>(&self, index: usize, name: &'static str) -> Result<T, ObjError> where T::Err: Display {
+        match self.arguments().get(index) {
+            Some(token) => parse_number(self, token, token.text, name),
+            None => Err(self.missing(name)),
+        }
+    }
+
+    pub(crate) fn optional_number<T: FromStr>(&self, index: usize, name: &'static str, default: T) -> Result<T, ObjError> where T::Err: Display {
+        match self.arguments().get(index) {
+            Some(token) => parse_number(self, token, token.text, name),
+            None => Ok(default),
+        }
+    }
+}
+
+/// Parses the text, which is the token or a part of it.
+fn parse_number<T: FromStr>(line: &Line, token: &Token, text: &str, name: &'static str) -> Result<T, ObjError> where T::Err: Display {
+    text.parse().map_err(|err: T::Err| line.error(token.column, ObjErrorKind::BadNumber {
+        name,
+        description: err.to_string(),
+    }))
+}
+
+fn parse_vertex(line: &Line) -> Result<Vertex, ObjError> {
     Ok(Vertex {
-        x,
-        y,
-        z,
-        w,
+        x: line.number(0, "vertex x")?,
+        y: line.number(1, "vertex y")?,
+        z: line.number(2, "vertex z")?,
+        w: line.optional_number(3, "vertex w", 1.0)?,
     })
 }
 
-fn parse_vertex_normal(parts: &[&str]) -> Result<VertexNormal, SceneIOError> {
-    let x = parts[0].parse().map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to parse vertex normal x: {}", err),
-    })?;
-    let y = parts[1].parse().map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to parse vertex normal y: {}", err),
-    })?;
-    let z = parts[2].parse().map_err(|err| SceneIOError::FailedToReadObj {
-        description: format!("Failed to parse vertex normal z: {}", err),
-    })?;
+fn parse_texture_coordinates(line: &Line) -> Result<TextureCoordinates, ObjError> {
+    Ok(TextureCoordinates {
+        u: line.number(0, "texture coordinate u")?,
+        v: line.optional_number(1, "texture coordinate v", 0.0)?,
+        w: line.optional_number(2, "texture coordinate w", 0.0)?,
+    })
+}
 
+fn parse_vertex_normal(line: &Line) -> Result<VertexNormal, ObjError> {
     Ok(VertexNormal {
-        x,
-        y,
-        z,
+        x: line.number(0, "vertex normal x")?,
+        y: line.number(1, "vertex normal y")?,
+        z: line.number(2, "vertex normal z")?,
     })
 }
 
-fn parse_face(parts: &[&str]) -> Result<Face, SceneIOError> {
+/// Number of elements defined so far, which negative indices are relative to.
+struct IndexCounts {
+    vertices: usize,
+    texture_coordinates: usize,
+    vertex_normals: usize,
+}
+
+fn parse_face(line: &Line, counts: &IndexCounts) -> Result<Face, ObjError> {
+    if line.arguments().len() < 3 {
+        return Err(line.missing("at least 3 face elements"));
+    }
+
     let mut elements = Vec::new();
 
-    for part in parts {
-        elements.push(parse_face_element(part)?);
+    for token in line.arguments() {
+        elements.push(parse_face_element(line, token, counts)?);
     }
 
     Ok(Face {
@@ -108,31 +354,21 @@ fn parse_face(parts: &[&str]) -> Result<Face, SceneIOError> {
     })
 }
 
-fn parse_face_element(part: &str) -> Result<FaceElement, SceneIOError> {
-    let spl: Vec<&str> = part.split("/").collect();
-    let vertex_index = if spl[0] == "" { 0 } else {
-        match spl[0].parse() {
-            Ok(v) => v,
-            Err(err) => return Err(SceneIOError::FailedToReadObj {
-                description: format!("Failed to parse vertex index: {}", err),
-            })
-        }
-    };
-    let texture_index = if spl[1] == "" { 0 } else {
-        match spl[1].parse() {
-            Ok(v) => v,
-            Err(err) => return Err(SceneIOError::FailedToReadObj {
-                description: format!("Failed to parse texture index: {}", err),
-            })
-        }
+/// Parses `v`, `v/vt`, `v//vn` and `v/vt/vn` face elements. Missing texture and normal indices are 0.
+fn parse_face_element(line: &Line, token: &Token, counts: &IndexCounts) -> Result<FaceElement, ObjError> {
+    let spl: Vec<&str> = token.text.split('/').collect();
+    if spl.len() > 3 || spl[0].is_empty() {
+        return Err(line.error(token.column, ObjErrorKind::InvalidFaceElement { element: token.text.to_string() }));
+    }
+
+    let vertex_index = parse_index(line, token, spl[0], counts.vertices, "vertex index")?;
+    let texture_index = match spl.get(1) {
+        Some(index) if !index.is_empty() => parse_index(line, token, index, counts.texture_coordinates, "texture index")?,
+        _ => 0,
     };
-    let normal_index = if spl[2] == "" { 0 } else {
-        match spl[2].parse() {
-            Ok(v) => v,
-            Err(err) => return Err(SceneIOError::FailedToReadObj {
-                description: format!("Failed to parse normal index: {}", err),
-            })
-        }
+    let normal_index = match spl.get(2) {
+        Some(index) if !index.is_empty() => parse_index(line, token, index, counts.vertex_normals, "normal index")?,
+        _ => 0,
     };
 
     Ok(FaceElement {
@@ -140,4 +376,174 @@ fn parse_face_element(part: &str) -> Result<FaceElement, SceneIOError> {
         texture_index,
         normal_index,
     })
-}
\ No newline at end of file
+}
+
+/// Parses 1-based index, negative indices count back from the last element defined so far.
+fn parse_index(line: &Line, token: &Token, index: &str, count: usize, name: &'static str) -> Result<i32, ObjError> {
+    let index: i32 = parse_number(line, token, index, name)?;
+
+    let resolved = if index < 0 { count as i64 + index as i64 + 1 } else { index as i64 };
+    if resolved < 1 || resolved > count as i64 {
+        return Err(line.error(token.column, ObjErrorKind::IndexOutOfRange { name, index, count }));
+    }
+
+    Ok(resolved as i32)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const COUNTS: IndexCounts = IndexCounts {
+        vertices: 4,
+        texture_coordinates: 3,
+        vertex_normals: 2,
+    };
+
+    fn element(vertex_index: i32, texture_index: i32, normal_index: i32) -> FaceElement {
+        FaceElement {
+            vertex_index,
+            texture_index,
+            normal_index,
+        }
+    }
+
+    fn parse_element(text: &str) -> Result<FaceElement, ObjError> {
+        let line = Line::new(1, text);
+        parse_face_element(&line, &line.tokens[0], &COUNTS)
+    }
+
+    #[test]
+    fn vertex_only() {
+        assert_eq!(element(3, 0, 0), parse_element("3").unwrap());
+    }
+
+    #[test]
+    fn vertex_and_texture() {
+        assert_eq!(element(3, 2, 0), parse_element("3/2").unwrap());
+    }
+
+    #[test]
+    fn vertex_and_normal() {
+        assert_eq!(element(3, 0, 1), parse_element("3//1").unwrap());
+    }
+
+    #[test]
+    fn vertex_texture_and_normal() {
+        assert_eq!(element(3, 2, 1), parse_element("3/2/1").unwrap());
+    }
+
+    #[test]
+    fn negative_indices() {
+        assert_eq!(element(4, 3, 2), parse_element("-1/-1/-1").unwrap());
+        assert_eq!(element(1, 0, 1), parse_element("-4//-2").unwrap());
+        assert!(parse_element("-5").is_err());
+    }
+
+    #[test]
+    fn invalid_elements() {
+        assert!(parse_element("/1/1").is_err());
+        assert!(parse_element("0").is_err());
+        assert!(parse_element("1/1/1/1").is_err());
+        assert!(parse_element("a/1").is_err());
+    }
+
+    #[test]
+    fn texture_coordinates() {
+        let geometry = read_obj("vt 0.5\nvt 0.25 0.75\nvt 0.1 0.2 0.3\n".as_bytes()).unwrap();
+
+        assert_eq!(vec![
+            TextureCoordinates { u: 0.5, v: 0.0, w: 0.0 },
+            TextureCoordinates { u: 0.25, v: 0.75, w: 0.0 },
+            TextureCoordinates { u: 0.1, v: 0.2, w: 0.3 },
+        ], geometry.texture_coordinates);
+        assert!(read_obj("vt\n".as_bytes()).is_err());
+        assert!(read_obj("vt 0.1 x\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn faces_of_each_form() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\nf 1 2 3\nf 1/1 2/2 3/3\nf 1//1 2//1 3//1\nf -3/-3/-1 -2/-2/-1 -1/-1/-1\n";
+        let geometry = read_obj(obj.as_bytes()).unwrap();
+
+        assert_eq!(3, geometry.texture_coordinates.len());
+        assert_eq!(4, geometry.faces.len());
+        assert_eq!(vec![element(1, 1, 1), element(2, 2, 1), element(3, 3, 1)], geometry.faces[3].elements);
+    }
+
+    #[test]
+    fn face_materials() {
+        let resolver = crate::assets::MemoryResolver::new().with("assets/box.mtl", "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n");
+        let obj = "mtllib box.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 1 2 3\nusemtl missing\nf 1 2 3\nusemtl blue\nf 1 2 3\nusemtl red\nf 1 2 3\n";
+
+        let mesh = read_obj_mesh(obj.as_bytes(), "assets/box.obj", &resolver, None).unwrap();
+
+        assert_eq!(vec!["red", "blue"], mesh.materials.iter().map(|material| material.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec![None, Some(0), None, Some(1), Some(0)], mesh.face_materials);
+        assert_eq!(5, mesh.geometry.faces.len());
+
+        assert!(matches!(read_obj_mesh(obj.as_bytes(), "assets/box.obj", &resolver, Some(16)), Err(SceneIOError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn tokens_and_columns() {
+        assert_eq!(vec![
+            Token { text: "v", column: 3 },
+            Token { text: "1", column: 5 },
+            Token { text: "2.5", column: 9 },
+        ], tokenize("\t v\t1   2.5 \r"));
+        assert!(tokenize(" \t").is_empty());
+    }
+
+    #[test]
+    fn tolerant_formatting() {
+        let obj = "# exported\r\n  v  0\t0 0 \r\n#comment\r\nv 1 0 0# trailing comment\r\nv 0 \\\r\n 1 \\\n 0\r\n\r\nf 1 2 3\r\n";
+        let geometry = read_obj(obj.as_bytes()).unwrap();
+
+        assert_eq!(3, geometry.vertices.len());
+        assert!((1.0 - geometry.vertices[2].y).abs() < 0.00001);
+        assert_eq!(vec![element(1, 0, 0), element(2, 0, 0), element(3, 0, 0)], geometry.faces[0].elements);
+    }
+
+    #[test]
+    fn errors_have_line_and_column() {
+        assert_eq!("Failed to read obj file: line 2, column 7: Failed to parse vertex y: invalid float literal in \"v  0  x 0\"",
+            read_obj("v 0 0 0\nv  0  x 0\n".as_bytes()).unwrap_err().to_string());
+        assert_eq!("Failed to read obj file: line 1, column 7: Expected vertex z in \"v 0  0\"",
+            read_obj("v 0  0 \\\n\n".as_bytes()).unwrap_err().to_string());
+        assert_eq!("Failed to read obj file: line 3, column 7: Face element vertex index -2 is out of range, there are 1 defined before it in \"f 1 1 -2\"",
+            read_obj("v 0 0 0\n\nf 1 1 -2\n".as_bytes()).unwrap_err().to_string());
+    }
+
+    fn obj_error(obj: &str) -> ObjError {
+        read_obj_at(obj.as_bytes(), Some(Path::new("mesh.obj"))).unwrap_err()
+    }
+
+    #[test]
+    fn error_kinds() {
+        let error = obj_error("v 0 0 0\nvn 0 1,0 0\n");
+        assert_eq!((Some(Path::new("mesh.obj").to_path_buf()), 2, 6, "vn 0 1,0 0"), (error.path.clone(), error.line, error.column, error.text.as_str()));
+        assert!(matches!(error.source, ObjErrorKind::BadNumber { .. }));
+
+        assert!(matches!(obj_error("v 0 0\n").source, ObjErrorKind::TooFewComponents { .. }));
+        assert!(matches!(obj_error("v 0 0 0\nv 0 0 0\nf 1 2\n").source, ObjErrorKind::TooFewComponents { .. }));
+        assert!(matches!(obj_error("v 0 0 0\nf 1 1 2\n").source, ObjErrorKind::IndexOutOfRange { name: "vertex index", index: 2, count: 1 }));
+        assert!(matches!(obj_error("v 0 0 0\nf 1 1 1/1\n").source, ObjErrorKind::IndexOutOfRange { name: "texture index", index: 1, count: 0 }));
+        assert!(matches!(obj_error("v 0 0 0\nf 1 1 /1\n").source, ObjErrorKind::InvalidFaceElement { .. }));
+    }
+
+    #[test]
+    fn io_errors() {
+        let mut invalid_utf8 = b"v 0 0 0\n".to_vec();
+        invalid_utf8.extend_from_slice(b"v \xff\n");
+        let error = read_obj_at(invalid_utf8.as_slice(), Some(Path::new("mesh.obj"))).unwrap_err();
+        assert_eq!("mesh.obj, line 2: IO Error: stream did not contain valid UTF-8", error.to_string());
+        assert!(matches!(error.source, ObjErrorKind::IOError { .. }));
+
+        let error = read_obj_file("./examples/assets/missing.obj").unwrap_err();
+        let chain: Vec<String> = std::iter::successors(Some(&error as &dyn std::error::Error), |err| err.source()).map(|err| err.to_string()).collect();
+        assert_eq!(4, chain.len(), "{:?}", chain);
+        assert!(chain[1].starts_with("./examples/assets/missing.obj: IO Error"), "{:?}", chain);
+    }
+}
@@ -0,0 +1,645 @@
+use std::collections::HashMap;
+use std::ops::Mul;
+
+use crate::errors::HierarchyError;
+use crate::hierarchy::Hierarchy;
+use crate::{scene_object, Scene, SceneObject, Transform, Vector3};
+
+const TOLERANCE: f64 = 0.00001;
+
+/// 4x4 matrix stored as rows, to be used with column vectors (`p' = M * p`).
+///
+/// With this convention right, up and forward vectors are the 1st, 2nd and 3rd columns
+/// of the matrix and position is the 4th column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+
+    pub fn identity() -> Self {
+        Matrix4 {
+            rows: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn translation(v: &Vector3) -> Self {
+        let mut m = Self::identity();
+        m.rows[0][3] = v.x;
+        m.rows[1][3] = v.y;
+        m.rows[2][3] = v.z;
+        m
+    }
+
+    pub fn scaling(v: &Vector3) -> Self {
+        let mut m = Self::identity();
+        m.rows[0][0] = v.x;
+        m.rows[1][1] = v.y;
+        m.rows[2][2] = v.z;
+        m
+    }
+
+    /// Rotation from euler angles in degrees, measured counterclockwise.
+    /// Rotation around x is applied first, then around y, then around z.
+    pub fn rotation(v: &Vector3) -> Self {
+        Self::rotation_z(v.z) * Self::rotation_y(v.y) * Self::rotation_x(v.x)
+    }
+
+    pub fn rotation_x(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut m = Self::identity();
+        m.rows[1][1] = cos;
+        m.rows[1][2] = -sin;
+        m.rows[2][1] = sin;
+        m.rows[2][2] = cos;
+        m
+    }
+
+    pub fn rotation_y(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut m = Self::identity();
+        m.rows[0][0] = cos;
+        m.rows[0][2] = sin;
+        m.rows[2][0] = -sin;
+        m.rows[2][2] = cos;
+        m
+    }
+
+    pub fn rotation_z(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut m = Self::identity();
+        m.rows[0][0] = cos;
+        m.rows[0][1] = -sin;
+        m.rows[1][0] = sin;
+        m.rows[1][1] = cos;
+        m
+    }
+
+    fn column(&self, index: usize) -> Vector3 {
+        Vector3 {
+            x: self.rows[0][index],
+            y: self.rows[1][index],
+            z: self.rows[2][index],
+        }
+    }
+
+    pub fn right(&self) -> Vector3 {
+        self.column(0)
+    }
+
+    pub fn up(&self) -> Vector3 {
+        self.column(1)
+    }
+
+    /// Camera viewport is located along the negative direction of this vector.
+    pub fn forward(&self) -> Vector3 {
+        self.column(2)
+    }
+
+    pub fn position(&self) -> Vector3 {
+        self.column(3)
+    }
+
+    pub fn transform_point(&self, p: &Vector3) -> Vector3 {
+        let r = &self.rows;
+        Vector3 {
+            x: r[0][0] * p.x + r[0][1] * p.y + r[0][2] * p.z + r[0][3],
+            y: r[1][0] * p.x + r[1][1] * p.y + r[1][2] * p.z + r[1][3],
+            z: r[2][0] * p.x + r[2][1] * p.y + r[2][2] * p.z + r[2][3],
+        }
+    }
+
+    pub fn transform_vector(&self, v: &Vector3) -> Vector3 {
+        let r = &self.rows;
+        Vector3 {
+            x: r[0][0] * v.x + r[0][1] * v.y + r[0][2] * v.z,
+            y: r[1][0] * v.x + r[1][1] * v.y + r[1][2] * v.z,
+            z: r[2][0] * v.x + r[2][1] * v.y + r[2][2] * v.z,
+        }
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[i][k] * other.rows[k][j]).sum();
+            }
+        }
+        Matrix4 { rows }
+    }
+}
+
+/// Matrix of transform relative to its parent: translation * rotation * scale.
+/// Unset position, rotation and scale are treated as their documented defaults.
+pub fn local_matrix(transform: &Transform) -> Matrix4 {
+    let mut m = Matrix4::identity();
+
+    if let Some(position) = &transform.position {
+        m = m * Matrix4::translation(position);
+    }
+
+    if let Some(rotation) = &transform.rotation {
+        m = m * Matrix4::rotation(rotation);
+    }
+
+    if let Some(scale) = &transform.scale {
+        m = m * Matrix4::scaling(scale);
+    }
+
+    m
+}
+
+/// Local and world matrices of every SceneObject, Light and Camera in a scene, by id.
+#[derive(Clone, Debug, Default)]
+pub struct SceneTransforms {
+    local: HashMap<i32, Matrix4>,
+    world: HashMap<i32, Matrix4>,
+}
+
+impl SceneTransforms {
+
+    pub fn new(scene: &Scene) -> Result<Self, HierarchyError> {
+        Self::with_hierarchy(scene, &Hierarchy::new(scene)?)
+    }
+
+    /// Same as `new`, reusing the hierarchy of the scene. Fails if the hierarchy has ids which are not in the scene.
+    pub fn with_hierarchy(scene: &Scene, hierarchy: &Hierarchy) -> Result<Self, HierarchyError> {
+        let local: HashMap<i32, Matrix4> = scene.scene_objects.iter().map(|o| (o.id, scene_object_local_matrix(o)))
+            .chain(scene.lights.iter().map(|l| (l.id, l.transform.as_ref().map(local_matrix).unwrap_or_default())))
+            .chain(scene.cameras.iter().map(|c| (c.id, c.transform.as_ref().map(local_matrix).unwrap_or_default())))
+            .collect();
+
+        let mut world = HashMap::new();
+        for &id in hierarchy.traversal_order() {
+            let local = *local.get(&id).ok_or(HierarchyError::UnknownId { id })?;
+            let matrix = match hierarchy.parent(id) {
+                Some(parent_id) => *world.get(&parent_id).ok_or(HierarchyError::UnknownParent { id, parent_id })? * local,
+                None => local,
+            };
+            world.insert(id, matrix);
+        }
+
+        Ok(SceneTransforms {
+            local,
+            world,
+        })
+    }
+
+    /// Matrix relative to the parent of the entity with given id.
+    pub fn local(&self, id: i32) -> Option<&Matrix4> {
+        self.local.get(&id)
+    }
+
+    /// Matrix in world space of the entity with given id, with all parent matrices applied.
+    pub fn world(&self, id: i32) -> Option<&Matrix4> {
+        self.world.get(&id)
+    }
+}
+
+/// Sphere and Cube ignore Transform.scale, their size is specified by radius and size instead.
+fn ignores_scale(scene_object: &SceneObject) -> bool {
+    matches!(scene_object.mesh, Some(scene_object::Mesh::Sphere(_)) | Some(scene_object::Mesh::Cube(_)))
+}
+
+fn scene_object_local_matrix(scene_object: &SceneObject) -> Matrix4 {
+    match &scene_object.transform {
+        Some(transform) if ignores_scale(scene_object) => local_matrix(&Transform {
+            scale: None,
+            ..transform.clone()
+        }),
+        Some(transform) => local_matrix(transform),
+        None => Matrix4::identity(),
+    }
+}
+
+/// Splits a matrix into position, euler rotation and scale, so that `local_matrix` of the
+/// result gives the same matrix.
+/// Returns `false` as the second value if the matrix contains shear, which happens when
+/// a rotated child has a parent with non-uniform scale, or a zero scale. Rotation is approximated then.
+pub fn decompose(m: &Matrix4) -> (Transform, bool) {
+    let columns = [m.right(), m.up(), m.forward()];
+
+    let mut scale = [length(&columns[0]), length(&columns[1]), length(&columns[2])];
+    let mut axes: Vec<Vector3> = columns.iter().zip(&scale)
+        .map(|(column, scale)| if *scale > 0.0 { multiply(column, 1.0 / scale) } else { column.clone() })
+        .collect();
+
+    let rebuilt = rebuild_zero_axes(&mut axes, &scale);
+    let exact = !rebuilt
+        && dot(&axes[0], &axes[1]).abs() < TOLERANCE
+        && dot(&axes[0], &axes[2]).abs() < TOLERANCE
+        && dot(&axes[1], &axes[2]).abs() < TOLERANCE;
+
+    if !exact {
+        // Gram-Schmidt to get the closest rotation keeping the right vector
+        let up = subtract(&axes[1], &multiply(&axes[0], dot(&axes[0], &axes[1])));
+        axes[1] = if length(&up) > TOLERANCE { normalize(&up) } else { orthogonal(&axes[0]) };
+        axes[2] = cross(&axes[0], &axes[1]);
+    }
+
+    if dot(&cross(&axes[0], &axes[1]), &axes[2]) < 0.0 {
+        scale[0] = -scale[0];
+        axes[0] = multiply(&axes[0], -1.0);
+    }
+
+    // rows of rotation matrix R = Rz * Ry * Rx
+    let r = |row: usize, column: usize| match row {
+        0 => axes[column].x,
+        1 => axes[column].y,
+        _ => axes[column].z,
+    };
+
+    let (x, y, z) = if r(2, 0).abs() < 1.0 - TOLERANCE {
+        (r(2, 1).atan2(r(2, 2)), (-r(2, 0)).asin(), r(1, 0).atan2(r(0, 0)))
+    } else {
+        // gimbal lock, rotation around z can be folded into rotation around x
+        ((-r(1, 2)).atan2(r(1, 1)), (-r(2, 0)).signum() * std::f64::consts::FRAC_PI_2, 0.0)
+    };
+
+    let transform = Transform {
+        parent_id: 0,
+        position: Some(m.position()),
+        rotation: Some(Vector3 {
+            x: x.to_degrees(),
+            y: y.to_degrees(),
+            z: z.to_degrees(),
+        }),
+        scale: Some(Vector3 {
+            x: scale[0],
+            y: scale[1],
+            z: scale[2],
+        }),
+    };
+
+    (transform, exact)
+}
+
+/// Zero scale leaves no direction for an axis, such axes are replaced with unit vectors orthogonal to the others.
+/// Returns whether any axis was replaced.
+fn rebuild_zero_axes(axes: &mut [Vector3], scale: &[f64; 3]) -> bool {
+    let zero: Vec<usize> = (0..3).filter(|&i| scale[i] < TOLERANCE).collect();
+    match zero[..] {
+        [] => return false,
+        [i] => {
+            let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+            let rebuilt = cross(&axes[j], &axes[k]);
+            axes[i] = if length(&rebuilt) > TOLERANCE { normalize(&rebuilt) } else { orthogonal(&axes[j]) };
+        },
+        [i, j] => {
+            let k = 3 - i - j;
+            axes[(k + 1) % 3] = orthogonal(&axes[k]);
+            axes[(k + 2) % 3] = cross(&axes[k], &axes[(k + 1) % 3]);
+        },
+        _ => {
+            axes[0] = Vector3 {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            axes[1] = Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            };
+            axes[2] = Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            };
+        },
+    }
+    true
+}
+
+/// Any unit vector orthogonal to the unit vector `v`.
+fn orthogonal(v: &Vector3) -> Vector3 {
+    // the basis vector least parallel to v can not give a zero cross product
+    let basis = if v.x.abs() <= v.y.abs() && v.x.abs() <= v.z.abs() {
+        Vector3 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    } else if v.y.abs() <= v.z.abs() {
+        Vector3 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        }
+    } else {
+        Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        }
+    };
+    normalize(&cross(v, &basis))
+}
+
+fn is_unit_scale(scale: &Vector3) -> bool {
+    (scale.x - 1.0).abs() < TOLERANCE && (scale.y - 1.0).abs() < TOLERANCE && (scale.z - 1.0).abs() < TOLERANCE
+}
+
+fn dot(a: &Vector3, b: &Vector3) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+fn cross(a: &Vector3, b: &Vector3) -> Vector3 {
+    Vector3 {
+        x: a.y * b.z - a.z * b.y,
+        y: a.z * b.x - a.x * b.z,
+        z: a.x * b.y - a.y * b.x,
+    }
+}
+
+fn multiply(v: &Vector3, k: f64) -> Vector3 {
+    Vector3 {
+        x: v.x * k,
+        y: v.y * k,
+        z: v.z * k,
+    }
+}
+
+fn subtract(a: &Vector3, b: &Vector3) -> Vector3 {
+    Vector3 {
+        x: a.x - b.x,
+        y: a.y - b.y,
+        z: a.z - b.z,
+    }
+}
+
+fn length(v: &Vector3) -> f64 {
+    dot(v, v).sqrt()
+}
+
+fn normalize(v: &Vector3) -> Vector3 {
+    multiply(v, 1.0 / length(v))
+}
+
+impl Scene {
+
+    /// Rewrites every transform with a parent into world space and sets its `parent_id` to 0.
+    ///
+    /// Sphere, Cube, Light and Camera keep scale of (1, 1, 1) as required by the spec, inherited scale is dropped.
+    /// Returns ids of entities whose world transform could only be approximated: the ones with shear,
+    /// which can not be expressed with position, rotation and scale, the ones with zero scale, whose rotation
+    /// is ambiguous, and the ones with dropped scale.
+    pub fn flatten_hierarchy(&mut self) -> Result<Vec<i32>, HierarchyError> {
+        let hierarchy = Hierarchy::new(self)?;
+        let transforms = SceneTransforms::with_hierarchy(self, &hierarchy)?;
+        let mut inexact = Vec::new();
+
+        let mut flatten = |id: i32, transform: &mut Option<Transform>, ignores_scale: bool| {
+            if hierarchy.parent(id).is_none() {
+                return;
+            }
+
+            let (mut world, mut exact) = decompose(&transforms.world[&id]);
+            if ignores_scale {
+                exact &= world.scale.as_ref().is_none_or(is_unit_scale);
+                world.scale = Some(Vector3 {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 1.0,
+                });
+            }
+            if !exact {
+                inexact.push(id);
+            }
+            *transform = Some(world);
+        };
+
+        for scene_object in &mut self.scene_objects {
+            let ignores_scale = ignores_scale(scene_object);
+            flatten(scene_object.id, &mut scene_object.transform, ignores_scale);
+        }
+
+        for light in &mut self.lights {
+            flatten(light.id, &mut light.transform, true);
+        }
+
+        for camera in &mut self.cameras {
+            flatten(camera.id, &mut camera.transform, true);
+        }
+
+        Ok(inexact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DELTA: f64 = 0.00001;
+
+    fn vector3(x: f64, y: f64, z: f64) -> Vector3 {
+        Vector3 {
+            x,
+            y,
+            z,
+        }
+    }
+
+    fn assert_vector_eq(expected: Vector3, actual: Vector3) {
+        assert!((expected.x - actual.x).abs() < DELTA, "expected {:?}, got {:?}", expected, actual);
+        assert!((expected.y - actual.y).abs() < DELTA, "expected {:?}, got {:?}", expected, actual);
+        assert!((expected.z - actual.z).abs() < DELTA, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn camera_basis_vectors() {
+        let scene = crate::read("./examples/7.cowscene").unwrap();
+        let transforms = SceneTransforms::new(&scene).unwrap();
+
+        let camera = transforms.world(1).unwrap();
+        assert_vector_eq(vector3(1.25, 0.1, 0.0), camera.position());
+        assert_vector_eq(vector3(0.0, 0.0, -1.0), camera.right());
+        // camera looks along negative forward, that is towards the origin
+        let forward = camera.forward();
+        assert!(forward.x > 0.9);
+        assert!(forward.y < 0.0);
+    }
+
+    #[test]
+    fn parent_matrices_are_applied() {
+        let mut scene = crate::read("./examples/1.cowscene").unwrap();
+        scene.scene_objects[0].transform = Some(Transform {
+            parent_id: 1,
+            position: Some(vector3(0.0, 0.0, -2.0)),
+            rotation: None,
+            scale: None,
+        });
+        scene.cameras[0].transform.as_mut().unwrap().rotation = Some(vector3(0.0, 90.0, 0.0));
+
+        let transforms = SceneTransforms::new(&scene).unwrap();
+
+        assert_vector_eq(vector3(0.0, 0.0, -2.0), transforms.local(2).unwrap().position());
+        assert_vector_eq(vector3(-2.0, 0.0, 6.0), transforms.world(2).unwrap().position());
+        assert_vector_eq(vector3(1.0, 0.0, 0.0), transforms.world(2).unwrap().forward());
+    }
+
+    #[test]
+    fn scale_is_applied_after_rotation() {
+        let m = local_matrix(&Transform {
+            parent_id: 0,
+            position: Some(vector3(1.0, 2.0, 3.0)),
+            rotation: Some(vector3(0.0, 0.0, 90.0)),
+            scale: Some(vector3(2.0, 1.0, 1.0)),
+        });
+
+        assert_vector_eq(vector3(0.0, 2.0, 0.0), m.right());
+        assert_vector_eq(vector3(-1.0, 0.0, 0.0), m.up());
+        assert_vector_eq(vector3(1.0, 4.0, 3.0), m.transform_point(&vector3(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn unknown_parent() {
+        let mut scene = crate::read("./examples/1.cowscene").unwrap();
+        scene.scene_objects[0].transform.as_mut().unwrap().parent_id = 42;
+
+        assert_eq!(HierarchyError::UnknownParent { id: 2, parent_id: 42 }, SceneTransforms::new(&scene).unwrap_err());
+    }
+
+    #[test]
+    fn hierarchy_of_other_scene() {
+        let scene = crate::read("./examples/11.cowscene").unwrap();
+        let hierarchy = Hierarchy::new(&scene).unwrap();
+        let mut other = scene.clone();
+        other.scene_objects.remove(0);
+
+        assert!(SceneTransforms::with_hierarchy(&scene, &hierarchy).is_ok());
+        assert_eq!(HierarchyError::UnknownId { id: 2 }, SceneTransforms::with_hierarchy(&other, &hierarchy).unwrap_err());
+    }
+
+    fn assert_matrix_eq(expected: &Matrix4, actual: &Matrix4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((expected.rows[i][j] - actual.rows[i][j]).abs() < DELTA, "expected {:?}, got {:?}", expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn decompose_restores_local_matrix() {
+        for rotation in &[vector3(10.0, 90.0, 0.0), vector3(-25.0, 25.0, 30.0), vector3(45.0, -90.0, 0.0), vector3(170.0, 20.0, -100.0)] {
+            let m = local_matrix(&Transform {
+                parent_id: 0,
+                position: Some(vector3(1.0, -2.0, 3.0)),
+                rotation: Some(rotation.clone()),
+                scale: Some(vector3(0.4, 1.0, 4.0)),
+            });
+
+            let (transform, exact) = decompose(&m);
+
+            assert!(exact);
+            assert_matrix_eq(&m, &local_matrix(&transform));
+        }
+    }
+
+    #[test]
+    fn flatten_hierarchy() {
+        let mut scene = crate::read("./examples/12.cowscene").unwrap();
+        scene.scene_objects[0].transform = Some(Transform {
+            parent_id: 1,
+            position: Some(vector3(0.0, 0.0, -2.0)),
+            rotation: Some(vector3(0.0, 45.0, 0.0)),
+            scale: Some(vector3(1.0, 1.0, 1.0)),
+        });
+        scene.scene_objects[1].transform = Some(Transform {
+            parent_id: 2,
+            position: Some(vector3(0.0, 1.0, 0.0)),
+            rotation: None,
+            scale: None,
+        });
+        let before = SceneTransforms::new(&scene).unwrap();
+
+        assert_eq!(Vec::<i32>::new(), scene.flatten_hierarchy().unwrap());
+
+        let after = SceneTransforms::new(&scene).unwrap();
+        for id in &[1, 2, 3, 4] {
+            assert_matrix_eq(before.world(*id).unwrap(), after.world(*id).unwrap());
+        }
+        assert_eq!(0, scene.scene_objects[0].transform.as_ref().unwrap().parent_id);
+        assert_eq!(0, scene.scene_objects[1].transform.as_ref().unwrap().parent_id);
+    }
+
+    #[test]
+    fn flatten_hierarchy_ignores_sphere_scale() {
+        let mut scene = crate::read("./examples/1.cowscene").unwrap();
+        scene.cameras[0].transform.as_mut().unwrap().scale = Some(vector3(2.0, 2.0, 2.0));
+        scene.scene_objects[0].transform = Some(Transform {
+            parent_id: 1,
+            position: Some(vector3(0.0, 0.0, -1.0)),
+            rotation: None,
+            scale: None,
+        });
+
+        scene.flatten_hierarchy().unwrap();
+
+        let transform = scene.scene_objects[0].transform.as_ref().unwrap();
+        assert_vector_eq(vector3(0.0, 0.0, 4.0), transform.position.clone().unwrap());
+        assert_vector_eq(vector3(1.0, 1.0, 1.0), transform.scale.clone().unwrap());
+    }
+
+    #[test]
+    fn flatten_hierarchy_reports_dropped_scale() {
+        let mut scene = crate::read("./examples/12.cowscene").unwrap();
+        scene.scene_objects[1].transform.as_mut().unwrap().scale = Some(vector3(2.0, 2.0, 2.0));
+        scene.scene_objects[0].transform.as_mut().unwrap().parent_id = 3;
+        scene.cameras[0].transform.as_mut().unwrap().parent_id = 3;
+
+        assert_eq!(vec![2, 1], scene.flatten_hierarchy().unwrap());
+
+        assert_vector_eq(vector3(1.0, 1.0, 1.0), scene.cameras[0].transform.clone().unwrap().scale.unwrap());
+        assert_eq!(Ok(()), crate::validate(&scene));
+    }
+
+    #[test]
+    fn flatten_hierarchy_with_zero_scale() {
+        let mut scene = crate::read("./examples/12.cowscene").unwrap();
+        scene.cameras[0].transform.as_mut().unwrap().scale = Some(vector3(0.0, 1.0, 1.0));
+        scene.scene_objects[1].transform.as_mut().unwrap().parent_id = 1;
+        let before = SceneTransforms::new(&scene).unwrap();
+
+        assert_eq!(vec![3], scene.flatten_hierarchy().unwrap());
+
+        let after = SceneTransforms::new(&scene).unwrap();
+        assert_matrix_eq(before.world(3).unwrap(), after.world(3).unwrap());
+
+        let transform = scene.scene_objects[1].transform.clone().unwrap();
+        for v in &[transform.position.unwrap(), transform.rotation.unwrap(), transform.scale.clone().unwrap()] {
+            assert!(v.x.is_finite() && v.y.is_finite() && v.z.is_finite(), "expected finite values, got {:?}", v);
+        }
+        assert_eq!(0.0, transform.scale.unwrap().x.abs());
+    }
+
+    #[test]
+    fn flatten_hierarchy_reports_shear() {
+        let mut scene = crate::read("./examples/7.cowscene").unwrap();
+        scene.cameras[0].transform = Some(Transform {
+            parent_id: 2,
+            position: None,
+            rotation: Some(vector3(0.0, 0.0, 45.0)),
+            scale: None,
+        });
+
+        assert_eq!(vec![1], scene.flatten_hierarchy().unwrap());
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashSet;
+
+use crate::errors::{HierarchyError, ValidationError};
+use crate::hierarchy::Hierarchy;
+use crate::index::SceneIndex;
+use crate::{
+    camera, light, material, scene_object, Camera, Color, Light, Material, RenderOptions, Scene,
+    SceneObject, Transform,
+};
+
+const TOLERANCE: f64 = 0.00001;
+
+/// Checks the scene against the rules of the format spec and returns every violation found.
+pub fn validate(scene: &Scene) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    let index = SceneIndex::new(scene);
+
+    if scene.version != 1 {
+        errors.push(ValidationError::UnsupportedVersion { version: scene.version });
+    }
+
+    let mut missing_fields = Vec::new();
+    scene.collect_missing_fields("", &mut missing_fields);
+    errors.extend(missing_fields.into_iter().map(|path| ValidationError::MissingField { path }));
+
+    let unique_ids = validate_ids(scene, &mut errors);
+
+    if let Some(render_options) = &scene.render_options {
+        validate_render_options(&index, render_options, &mut errors);
+    }
+
+    for scene_object in &scene.scene_objects {
+        validate_scene_object(&index, scene_object, &mut errors);
+    }
+
+    for light in &scene.lights {
+        validate_light(&index, light, &mut errors);
+    }
+
+    for camera in &scene.cameras {
+        validate_camera(&index, camera, &mut errors);
+    }
+
+    validate_materials(&scene.materials, &mut errors);
+
+    // unknown parents are already reported by validate_transform, the hierarchy is ambiguous with duplicate ids
+    if unique_ids {
+        if let Err(HierarchyError::ParentCycle { members }) = Hierarchy::new(scene) {
+            errors.push(ValidationError::ParentCycle { members });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns false if some ids are used more than once.
+fn validate_ids(scene: &Scene, errors: &mut Vec<ValidationError>) -> bool {
+    let ids = scene.cameras.iter().map(|camera| ("Camera", camera.id))
+        .chain(scene.lights.iter().map(|light| ("Light", light.id)))
+        .chain(scene.scene_objects.iter().map(|scene_object| ("SceneObject", scene_object.id)));
+
+    let mut seen = HashSet::new();
+    let mut unique = true;
+    for (kind, id) in ids {
+        if id <= 0 {
+            errors.push(ValidationError::InvalidId { kind, id });
+        } else if !seen.insert(id) {
+            errors.push(ValidationError::DuplicateId { kind, id });
+            unique = false;
+        }
+    }
+    unique
+}
+
+fn validate_render_options(index: &SceneIndex, render_options: &RenderOptions, errors: &mut Vec<ValidationError>) {
+    if index.camera(render_options.camera_id).is_none() {
+        errors.push(ValidationError::UnknownCamera { camera_id: render_options.camera_id });
+    }
+
+    if render_options.width <= 0 || render_options.height <= 0 {
+        errors.push(ValidationError::InvalidImageSize {
+            width: render_options.width,
+            height: render_options.height,
+        });
+    }
+}
+
+fn validate_scene_object(index: &SceneIndex, scene_object: &SceneObject, errors: &mut Vec<ValidationError>) {
+    let id = scene_object.id;
+
+    if let Some(transform) = &scene_object.transform {
+        validate_transform(index, "SceneObject", id, transform, errors);
+    }
+
+    match &scene_object.object_material {
+        Some(scene_object::ObjectMaterial::MaterialId(material_id))
+            if !material_id.is_empty() && index.material(material_id).is_none() => {
+            errors.push(ValidationError::UnknownMaterial {
+                object_id: id,
+                material_id: material_id.clone(),
+            });
+        },
+        Some(scene_object::ObjectMaterial::Material(material)) => {
+            validate_material(material, format!("material of scene object with id = {}", id), errors);
+        },
+        Some(scene_object::ObjectMaterial::MaterialId(_)) | None => {},
+    }
+
+    match &scene_object.mesh {
+        Some(scene_object::Mesh::Sphere(sphere)) => {
+            if sphere.radius <= 0.0 {
+                errors.push(ValidationError::InvalidRadius { kind: "Sphere", id });
+            }
+
+            if has_scale(scene_object.transform.as_ref()) {
+                errors.push(ValidationError::UnexpectedScale { kind: "Sphere", id });
+            }
+        },
+        Some(scene_object::Mesh::Cube(cube)) => {
+            if let Some(size) = &cube.size {
+                if size.x < 0.0 || size.y < 0.0 || size.z < 0.0 {
+                    errors.push(ValidationError::NegativeCubeSize { id });
+                }
+            }
+
+            if has_scale(scene_object.transform.as_ref()) {
+                errors.push(ValidationError::UnexpectedScale { kind: "Cube", id });
+            }
+        },
+        Some(scene_object::Mesh::Disk(disk)) => {
+            if disk.radius <= 0.0 {
+                errors.push(ValidationError::InvalidRadius { kind: "Disk", id });
+            }
+        },
+        Some(scene_object::Mesh::MeshedObject(meshed_object)) => {
+            if meshed_object.reference.is_empty() && meshed_object.obj.is_none() {
+                errors.push(ValidationError::MissingReference { id });
+            }
+        },
+        Some(scene_object::Mesh::Plane(_)) | None => {},
+    }
+}
+
+fn validate_light(index: &SceneIndex, light: &Light, errors: &mut Vec<ValidationError>) {
+    if let Some(transform) = &light.transform {
+        validate_transform(index, "Light", light.id, transform, errors);
+    }
+
+    if let Some(color) = &light.color {
+        validate_color(color, format!("light with id = {}", light.id), errors);
+    }
+
+    if has_scale(light.transform.as_ref()) {
+        errors.push(ValidationError::UnexpectedScale { kind: "Light", id: light.id });
+    }
+
+    if let Some(light::Light::Sphere(sphere)) = &light.light {
+        if sphere.radius <= 0.0 {
+            errors.push(ValidationError::InvalidRadius { kind: "LightSphere", id: light.id });
+        }
+    }
+}
+
+fn validate_camera(index: &SceneIndex, camera: &Camera, errors: &mut Vec<ValidationError>) {
+    if let Some(transform) = &camera.transform {
+        validate_transform(index, "Camera", camera.id, transform, errors);
+    }
+
+    if has_scale(camera.transform.as_ref()) {
+        errors.push(ValidationError::UnexpectedScale { kind: "Camera", id: camera.id });
+    }
+
+    if let Some(camera::Camera::Perspective(perspective)) = &camera.camera {
+        if perspective.fov <= 0.0 || perspective.fov >= 180.0 {
+            errors.push(ValidationError::InvalidFov { id: camera.id, fov: perspective.fov });
+        }
+    }
+}
+
+fn validate_transform(index: &SceneIndex, kind: &'static str, id: i32, transform: &Transform, errors: &mut Vec<ValidationError>) {
+    if transform.parent_id != 0 && index.entity(transform.parent_id).is_none() {
+        errors.push(ValidationError::UnknownParent { kind, id, parent_id: transform.parent_id });
+    }
+
+    if let Some(scale) = &transform.scale {
+        if scale.x < 0.0 || scale.y < 0.0 || scale.z < 0.0 {
+            errors.push(ValidationError::NegativeScale { kind, id });
+        }
+    }
+}
+
+fn validate_materials(materials: &[Material], errors: &mut Vec<ValidationError>) {
+    let mut ids = HashSet::new();
+
+    for material in materials {
+        if !material.id.is_empty() && !ids.insert(material.id.as_str()) {
+            errors.push(ValidationError::DuplicateMaterialId { material_id: material.id.clone() });
+        }
+
+        validate_material(material, format!("material id = \"{}\"", material.id), errors);
+    }
+}
+
+fn validate_material(material: &Material, owner: String, errors: &mut Vec<ValidationError>) {
+    if let Some(material::Material::LambertReflection(lambert)) = &material.material {
+        if let Some(color) = &lambert.color {
+            validate_color(color, owner, errors);
+        }
+    }
+}
+
+fn validate_color(color: &Color, owner: String, errors: &mut Vec<ValidationError>) {
+    if color.r < 0.0 || color.g < 0.0 || color.b < 0.0 {
+        errors.push(ValidationError::NegativeColor { owner });
+    }
+}
+
+fn has_scale(transform: Option<&Transform>) -> bool {
+    match transform.and_then(|transform| transform.scale.as_ref()) {
+        Some(scale) => (scale.x - 1.0).abs() > TOLERANCE
+            || (scale.y - 1.0).abs() > TOLERANCE
+            || (scale.z - 1.0).abs() > TOLERANCE,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{LambertReflectionMaterial, PerspectiveCamera, Sphere, Vector3};
+
+    fn valid_scene() -> Scene {
+        Scene {
+            version: 1,
+            render_options: Some(RenderOptions {
+                camera_id: 1,
+                width: 640,
+                height: 360,
+                custom_properties: Vec::new(),
+            }),
+            scene_objects: vec![
+                SceneObject {
+                    id: 2,
+                    transform: None,
+                    object_material: Some(scene_object::ObjectMaterial::MaterialId("white".to_string())),
+                    mesh: Some(scene_object::Mesh::Sphere(Sphere {
+                        radius: 1.0,
+                    })),
+                },
+            ],
+            lights: vec![],
+            cameras: vec![
+                Camera {
+                    id: 1,
+                    transform: None,
+                    camera: Some(camera::Camera::Perspective(PerspectiveCamera {
+                        fov: 60.0,
+                    })),
+                },
+            ],
+            materials: vec![
+                Material {
+                    id: "white".to_string(),
+                    material: Some(material::Material::LambertReflection(LambertReflectionMaterial {
+                        color: Some(Color {
+                            r: 1.0,
+                            g: 1.0,
+                            b: 1.0,
+                        }),
+                    })),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn valid_scene_passes() {
+        assert_eq!(Ok(()), validate(&valid_scene()));
+    }
+
+    #[test]
+    fn reports_all_errors() {
+        let mut scene = valid_scene();
+        scene.render_options.as_mut().unwrap().camera_id = 7;
+        scene.cameras[0].camera = Some(camera::Camera::Perspective(PerspectiveCamera {
+            fov: 180.0,
+        }));
+        scene.scene_objects[0].id = 1;
+        scene.scene_objects[0].object_material = Some(scene_object::ObjectMaterial::MaterialId("red".to_string()));
+        scene.scene_objects[0].transform = Some(Transform {
+            parent_id: 42,
+            position: None,
+            rotation: None,
+            scale: Some(Vector3 {
+                x: -1.0,
+                y: 1.0,
+                z: 1.0,
+            }),
+        });
+
+        assert_eq!(Err(vec![
+            ValidationError::DuplicateId { kind: "SceneObject", id: 1 },
+            ValidationError::UnknownCamera { camera_id: 7 },
+            ValidationError::UnknownParent { kind: "SceneObject", id: 1, parent_id: 42 },
+            ValidationError::NegativeScale { kind: "SceneObject", id: 1 },
+            ValidationError::UnknownMaterial { object_id: 1, material_id: "red".to_string() },
+            ValidationError::UnexpectedScale { kind: "Sphere", id: 1 },
+            ValidationError::InvalidFov { id: 1, fov: 180.0 },
+        ]), validate(&scene));
+    }
+
+    #[test]
+    fn missing_required_fields() {
+        let mut scene = valid_scene();
+        scene.materials[0].material = Some(material::Material::LambertReflection(LambertReflectionMaterial {
+            color: None,
+        }));
+        scene.render_options = None;
+
+        assert_eq!(Err(vec![
+            ValidationError::MissingField { path: "render_options".to_string() },
+            ValidationError::MissingField { path: "materials[0].lambert_reflection.color".to_string() },
+        ]), validate(&scene));
+    }
+
+    #[test]
+    fn material_and_camera_kind_are_not_required() {
+        let mut scene = valid_scene();
+        scene.scene_objects[0].object_material = None;
+        scene.cameras[0].camera = None;
+
+        assert_eq!(Ok(()), validate(&scene));
+    }
+
+    #[test]
+    fn parent_cycle() {
+        let mut scene = valid_scene();
+        scene.cameras[0].transform = Some(Transform {
+            parent_id: 2,
+            ..Transform::default()
+        });
+        scene.scene_objects[0].transform = Some(Transform {
+            parent_id: 1,
+            ..Transform::default()
+        });
+
+        assert_eq!(Err(vec![
+            ValidationError::ParentCycle { members: vec![2, 1] },
+        ]), validate(&scene));
+    }
+
+    #[test]
+    fn duplicate_ids_with_parents() {
+        let mut scene = valid_scene();
+        scene.scene_objects.push(SceneObject {
+            id: 2,
+            transform: Some(Transform {
+                parent_id: 3,
+                ..Transform::default()
+            }),
+            ..scene.scene_objects[0].clone()
+        });
+        scene.lights.push(Light {
+            id: 3,
+            transform: Some(Transform {
+                parent_id: 2,
+                ..Transform::default()
+            }),
+            color: None,
+            light: Some(light::Light::Point(crate::LightPoint {})),
+        });
+
+        assert_eq!(Err(vec![
+            ValidationError::DuplicateId { kind: "SceneObject", id: 2 },
+        ]), validate(&scene));
+    }
+
+    #[test]
+    fn invalid_radius_owner() {
+        assert_eq!("LightSphere should have a positive radius, light id = 3",
+            ValidationError::InvalidRadius { kind: "LightSphere", id: 3 }.to_string());
+        assert_eq!("Disk should have a positive radius, scene object id = 2",
+            ValidationError::InvalidRadius { kind: "Disk", id: 2 }.to_string());
+    }
+
+    #[test]
+    fn negative_light_color() {
+        let mut scene = valid_scene();
+        scene.lights.push(Light {
+            id: 3,
+            transform: None,
+            color: Some(Color {
+                r: -1.0,
+                g: 1.0,
+                b: 1.0,
+            }),
+            light: Some(light::Light::Point(crate::LightPoint {})),
+        });
+
+        assert_eq!(Err(vec![
+            ValidationError::NegativeColor { owner: "light with id = 3".to_string() },
+        ]), validate(&scene));
+    }
+}